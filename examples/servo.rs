@@ -20,9 +20,9 @@ fn main() {
     let mut servo = Servo::new(23);
 
     while RUNNING.load(Ordering::Relaxed) {
-        servo.max();
+        servo.max().unwrap();
         thread::sleep(Duration::from_millis(2_000));
-        servo.min();
+        servo.min().unwrap();
         thread::sleep(Duration::from_millis(2_000));
     }
 }
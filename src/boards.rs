@@ -0,0 +1,1073 @@
+//! Composite devices built from several output devices, such as `LEDBoard`, `TrafficLights`
+//! and `Robot`
+use crate::devices::CompositeDevices;
+use crate::error::{Error, Result};
+use crate::output_devices::{Motor, PhaseEnableMotor, LED, PWMLED};
+use crate::source::{check_delay, SourcePump, DEFAULT_SOURCE_DELAY};
+use std::collections::HashMap;
+
+/// An LED of an `LEDBoard`: a `PWMLED` if the board was created with PWM, an `LED` otherwise.
+#[derive(Debug)]
+pub enum BoardLED {
+    /// An LED that can only be turned on and off
+    LED(LED),
+    /// An LED with variable brightness
+    PWMLED(PWMLED),
+}
+
+impl BoardLED {
+    /// Turns the LED on.
+    pub fn on(&mut self) -> Result<()> {
+        match self {
+            BoardLED::LED(led) => {
+                led.on();
+                Ok(())
+            }
+            BoardLED::PWMLED(led) => led.on(),
+        }
+    }
+
+    /// Turns the LED off.
+    pub fn off(&mut self) -> Result<()> {
+        match self {
+            BoardLED::LED(led) => {
+                led.off();
+                Ok(())
+            }
+            BoardLED::PWMLED(led) => led.off(),
+        }
+    }
+
+    /// Reverse the state of the LED. A `PWMLED` with a value of 0.1 is toggled to 0.9.
+    pub fn toggle(&mut self) -> Result<()> {
+        match self {
+            BoardLED::LED(led) => {
+                led.toggle();
+                Ok(())
+            }
+            BoardLED::PWMLED(led) => led.toggle(),
+        }
+    }
+
+    /// Returns ``True`` if the LED is currently lit and ``False`` otherwise.
+    pub fn is_lit(&self) -> bool {
+        match self {
+            BoardLED::LED(led) => led.is_lit(),
+            BoardLED::PWMLED(led) => led.is_lit(),
+        }
+    }
+
+    /// The brightness of the LED, between 0.0 (off) and 1.0 (fully on).
+    pub fn value(&self) -> f64 {
+        match self {
+            BoardLED::LED(led) => f64::from(u8::from(led.is_lit())),
+            BoardLED::PWMLED(led) => led.value(),
+        }
+    }
+
+    /// Set the brightness of the LED. Returns `Error::OutOfRange` if `value` is not between
+    /// 0.0 and 1.0, or `Error::Unsupported` for fractional values without PWM.
+    pub fn set_value(&mut self, value: f64) -> Result<()> {
+        self.check_value(value)?;
+        match self {
+            BoardLED::LED(led) if value == 0.0 => {
+                led.off();
+                Ok(())
+            }
+            BoardLED::LED(led) => {
+                led.on();
+                Ok(())
+            }
+            BoardLED::PWMLED(led) => led.set_value(value),
+        }
+    }
+
+    /// Returns the error `set_value` would return for `value`, without changing the LED
+    fn check_value(&self, value: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&value) {
+            return Err(Error::OutOfRange {
+                value,
+                min: 0.0,
+                max: 1.0,
+            });
+        }
+        match self {
+            BoardLED::LED(_) if value != 0.0 && value != 1.0 => {
+                Err(Error::Unsupported("fractional values without PWM"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Set the number of times to blink the LED
+    /// * `n` - Number of times to blink
+    pub fn set_blink_count(&mut self, n: i32) {
+        match self {
+            BoardLED::LED(led) => led.set_blink_count(n),
+            BoardLED::PWMLED(led) => led.set_blink_count(n),
+        }
+    }
+
+    /// Make the LED turn on and off repeatedly in the background.
+    /// Returns `Error::Unsupported` if fade times are given without PWM.
+    /// * `on_time` - Number of seconds on
+    /// * `off_time` - Number of seconds off
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    pub fn blink(
+        &mut self,
+        on_time: f32,
+        off_time: f32,
+        fade_in_time: f32,
+        fade_out_time: f32,
+    ) -> Result<()> {
+        match self {
+            BoardLED::LED(_) if fade_in_time > 0.0 || fade_out_time > 0.0 => {
+                Err(Error::Unsupported("fading without PWM"))
+            }
+            BoardLED::LED(led) => {
+                led.blink(on_time, off_time);
+                Ok(())
+            }
+            BoardLED::PWMLED(led) => {
+                led.blink(on_time, off_time, fade_in_time, fade_out_time);
+                Ok(())
+            }
+        }
+    }
+
+    /// Make the LED fade in and out repeatedly in the background.
+    /// Returns `Error::Unsupported` without PWM.
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    pub fn pulse(&mut self, fade_in_time: f32, fade_out_time: f32) -> Result<()> {
+        match self {
+            BoardLED::LED(_) => Err(Error::Unsupported("pulse without PWM")),
+            BoardLED::PWMLED(led) => {
+                led.pulse(fade_in_time, fade_out_time);
+                Ok(())
+            }
+        }
+    }
+
+    /// Block until the LED has finished blinking
+    pub fn wait(&mut self) {
+        match self {
+            BoardLED::LED(led) => led.wait(),
+            BoardLED::PWMLED(led) => led.wait(),
+        }
+    }
+}
+
+/// Represents a generic LED board or collection of LEDs.
+/// LEDs can be accessed by their index, in the order of the pins given, or by name
+/// for boards created with `new_named`.
+///
+/// # Example
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut board = LEDBoard::new_named(&[("red", 17), ("green", 18)]);
+/// board.on().unwrap();
+/// board.named_mut("red").unwrap().off().unwrap();
+/// assert_eq!(board.value(), vec![0.0, 1.0]);
+/// assert_eq!(factory.get(18).value(), 1.0);
+/// assert!(board.is_active());
+/// ```
+#[derive(Debug)]
+pub struct LEDBoard {
+    leds: Vec<BoardLED>,
+    names: HashMap<String, usize>,
+    pwm: bool,
+}
+
+impl LEDBoard {
+    /// Returns an LEDBoard with an `LED` for each of the pin numbers given
+    ///
+    /// * `pins` - The GPIO pins which the LEDs are attached to
+    pub fn new(pins: &[u8]) -> LEDBoard {
+        match LEDBoard::try_new(pins) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new(pins: &[u8]) -> Result<LEDBoard> {
+        LEDBoard::with_leds(pins.iter().map(|&pin| (None, pin)), false)
+    }
+
+    /// Returns an LEDBoard with a `PWMLED` for each of the pin numbers given
+    ///
+    /// * `pins` - The GPIO pins which the LEDs are attached to
+    pub fn new_with_pwm(pins: &[u8]) -> LEDBoard {
+        match LEDBoard::try_new_with_pwm(pins) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_with_pwm`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new_with_pwm(pins: &[u8]) -> Result<LEDBoard> {
+        LEDBoard::with_leds(pins.iter().map(|&pin| (None, pin)), true)
+    }
+
+    /// Returns an LEDBoard with an `LED` for each of the `(name, pin)` pairs given
+    ///
+    /// * `leds` - The names of the LEDs and the GPIO pins which they are attached to
+    pub fn new_named(leds: &[(&str, u8)]) -> LEDBoard {
+        match LEDBoard::try_new_named(leds) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_named`, but returns an error instead of panicking if a pin is not available,
+    /// or `Error::DuplicateName` if two LEDs have the same name
+    pub fn try_new_named(leds: &[(&str, u8)]) -> Result<LEDBoard> {
+        LEDBoard::with_leds(leds.iter().map(|&(name, pin)| (Some(name), pin)), false)
+    }
+
+    /// Returns an LEDBoard with a `PWMLED` for each of the `(name, pin)` pairs given
+    ///
+    /// * `leds` - The names of the LEDs and the GPIO pins which they are attached to
+    pub fn new_named_with_pwm(leds: &[(&str, u8)]) -> LEDBoard {
+        match LEDBoard::try_new_named_with_pwm(leds) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_named_with_pwm`, but returns an error instead of panicking if a pin is not
+    /// available, or `Error::DuplicateName` if two LEDs have the same name
+    pub fn try_new_named_with_pwm(leds: &[(&str, u8)]) -> Result<LEDBoard> {
+        LEDBoard::with_leds(leds.iter().map(|&(name, pin)| (Some(name), pin)), true)
+    }
+
+    fn with_leds<'a, I>(leds: I, pwm: bool) -> Result<LEDBoard>
+    where
+        I: IntoIterator<Item = (Option<&'a str>, u8)>,
+    {
+        let mut board = LEDBoard {
+            leds: Vec::new(),
+            names: HashMap::new(),
+            pwm,
+        };
+        for (name, pin) in leds {
+            if let Some(name) = name {
+                if board.names.contains_key(name) {
+                    return Err(Error::DuplicateName(name.to_string()));
+                }
+                board.names.insert(name.to_string(), board.leds.len());
+            }
+            board.leds.push(if pwm {
+                BoardLED::PWMLED(PWMLED::try_new(pin)?)
+            } else {
+                BoardLED::LED(LED::try_new(pin)?)
+            });
+        }
+        Ok(board)
+    }
+
+    /// Returns ``True`` if the LEDs of the board are `PWMLED`s.
+    pub fn pwm(&self) -> bool {
+        self.pwm
+    }
+
+    /// Returns the LED at `index`, in the order of the pins given when creating the board.
+    pub fn led(&self, index: usize) -> Option<&BoardLED> {
+        self.leds.get(index)
+    }
+
+    /// Returns the LED at `index` mutably, to control it independently of the rest of the board.
+    pub fn led_mut(&mut self, index: usize) -> Option<&mut BoardLED> {
+        self.leds.get_mut(index)
+    }
+
+    /// Returns the LED with the given name.
+    pub fn named(&self, name: &str) -> Option<&BoardLED> {
+        let index = *self.names.get(name)?;
+        self.leds.get(index)
+    }
+
+    /// Returns the LED with the given name mutably, to control it independently of the rest of the board.
+    pub fn named_mut(&mut self, name: &str) -> Option<&mut BoardLED> {
+        let index = *self.names.get(name)?;
+        self.leds.get_mut(index)
+    }
+
+    /// Returns an iterator over the LEDs of the board.
+    pub fn leds(&self) -> impl Iterator<Item = &BoardLED> {
+        self.leds.iter()
+    }
+
+    /// Returns an iterator over the LEDs of the board, allowing to modify them.
+    pub fn leds_mut(&mut self) -> impl Iterator<Item = &mut BoardLED> {
+        self.leds.iter_mut()
+    }
+
+    /// Turn all the LEDs on.
+    pub fn on(&mut self) -> Result<()> {
+        self.leds.iter_mut().try_for_each(BoardLED::on)
+    }
+
+    /// Turn all the LEDs off.
+    pub fn off(&mut self) -> Result<()> {
+        self.leds.iter_mut().try_for_each(BoardLED::off)
+    }
+
+    /// Toggle all the LEDs. For each LED, if it's on, turn it off; if it's off, turn it on.
+    pub fn toggle(&mut self) -> Result<()> {
+        self.leds.iter_mut().try_for_each(BoardLED::toggle)
+    }
+
+    /// The values of all the LEDs, in the order of the pins given when creating the board.
+    pub fn value(&self) -> Vec<f64> {
+        self.leds.iter().map(BoardLED::value).collect()
+    }
+
+    /// Set the values of all the LEDs, in the order of the pins given when creating the board.
+    /// Returns `Error::WrongValueCount` if the number of values differs from the number of LEDs,
+    /// or the error of the first invalid value (see `BoardLED::set_value`), without changing
+    /// any LED.
+    pub fn set_value(&mut self, values: &[f64]) -> Result<()> {
+        if values.len() != self.leds.len() {
+            return Err(Error::WrongValueCount {
+                expected: self.leds.len(),
+                actual: values.len(),
+            });
+        }
+        self.leds
+            .iter()
+            .zip(values)
+            .try_for_each(|(led, &value)| led.check_value(value))?;
+        self.leds
+            .iter_mut()
+            .zip(values)
+            .try_for_each(|(led, &value)| led.set_value(value))
+    }
+
+    /// Set the number of times to blink the LEDs
+    /// * `n` - Number of times to blink
+    pub fn set_blink_count(&mut self, n: i32) {
+        for led in &mut self.leds {
+            led.set_blink_count(n)
+        }
+    }
+
+    /// Make all the LEDs turn on and off repeatedly in the background.
+    /// Returns `Error::Unsupported` if fade times are given without PWM.
+    /// * `on_time` - Number of seconds on
+    /// * `off_time` - Number of seconds off
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    pub fn blink(
+        &mut self,
+        on_time: f32,
+        off_time: f32,
+        fade_in_time: f32,
+        fade_out_time: f32,
+    ) -> Result<()> {
+        if !self.pwm && (fade_in_time > 0.0 || fade_out_time > 0.0) {
+            return Err(Error::Unsupported("fading without PWM"));
+        }
+        self.leds
+            .iter_mut()
+            .try_for_each(|led| led.blink(on_time, off_time, fade_in_time, fade_out_time))
+    }
+
+    /// Make all the LEDs fade in and out repeatedly in the background.
+    /// Returns `Error::Unsupported` without PWM.
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    pub fn pulse(&mut self, fade_in_time: f32, fade_out_time: f32) -> Result<()> {
+        if !self.pwm {
+            return Err(Error::Unsupported("pulse without PWM"));
+        }
+        self.leds
+            .iter_mut()
+            .try_for_each(|led| led.pulse(fade_in_time, fade_out_time))
+    }
+
+    /// Block until all the LEDs have finished blinking
+    pub fn wait(&mut self) {
+        for led in &mut self.leds {
+            led.wait()
+        }
+    }
+}
+
+impl CompositeDevices for LEDBoard {
+    fn close(self) {
+        drop(self)
+    }
+
+    fn is_active(&self) -> bool {
+        self.leds.iter().any(BoardLED::is_lit)
+    }
+
+    fn len(&self) -> usize {
+        self.leds.len()
+    }
+}
+
+/// Represents a bar graph of LEDs, lighting a proportion of the LEDs to represent a value.
+/// Positive values light the LEDs from the first pin given, negative values from the last one.
+/// With PWM, the last lit LED is dimmed to represent the fractional part of the value.
+///
+/// # Example
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut graph = LEDBarGraph::new(&[17, 18, 19, 20]);
+/// graph.set_value(0.5).unwrap();
+/// assert_eq!(graph.leds(), vec![1.0, 1.0, 0.0, 0.0]);
+/// graph.set_value(-0.25).unwrap();
+/// assert_eq!(graph.leds(), vec![0.0, 0.0, 0.0, 1.0]);
+/// assert_eq!(factory.get(20).value(), 1.0);
+/// ```
+#[derive(Debug)]
+pub struct LEDBarGraph {
+    board: LEDBoard,
+    value: f64,
+}
+
+impl LEDBarGraph {
+    /// Returns an LEDBarGraph with an `LED` for each of the pin numbers given
+    ///
+    /// * `pins` - The GPIO pins which the LEDs are attached to, from the bottom of the graph
+    pub fn new(pins: &[u8]) -> LEDBarGraph {
+        match LEDBarGraph::try_new(pins) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new(pins: &[u8]) -> Result<LEDBarGraph> {
+        Ok(LEDBarGraph {
+            board: LEDBoard::try_new(pins)?,
+            value: 0.0,
+        })
+    }
+
+    /// Returns an LEDBarGraph with a `PWMLED` for each of the pin numbers given
+    ///
+    /// * `pins` - The GPIO pins which the LEDs are attached to, from the bottom of the graph
+    pub fn new_with_pwm(pins: &[u8]) -> LEDBarGraph {
+        match LEDBarGraph::try_new_with_pwm(pins) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_with_pwm`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new_with_pwm(pins: &[u8]) -> Result<LEDBarGraph> {
+        Ok(LEDBarGraph {
+            board: LEDBoard::try_new_with_pwm(pins)?,
+            value: 0.0,
+        })
+    }
+
+    /// The value represented by the graph, between -1.0 and 1.0.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Light the proportion of LEDs representing `value`. Returns `Error::OutOfRange`
+    /// if `value` is not between -1.0 and 1.0.
+    pub fn set_value(&mut self, value: f64) -> Result<()> {
+        if !(-1.0..=1.0).contains(&value) {
+            return Err(Error::OutOfRange {
+                value,
+                min: -1.0,
+                max: 1.0,
+            });
+        }
+        let count = self.board.len();
+        let pwm = self.board.pwm();
+        let levels: Vec<f64> = (0..count)
+            .map(|index| {
+                let position = if value >= 0.0 {
+                    index
+                } else {
+                    count - 1 - index
+                };
+                let level = (value.abs() * count as f64 - position as f64).clamp(0.0, 1.0);
+                if pwm {
+                    level
+                } else {
+                    level.floor()
+                }
+            })
+            .collect();
+        self.board.set_value(&levels)?;
+        self.value = value;
+        Ok(())
+    }
+
+    /// The number of LEDs lit by the current value. Negative when lit from the last LED.
+    /// The value has a fractional part when the last lit LED is dimmed.
+    pub fn lit_count(&self) -> f64 {
+        self.value * self.board.len() as f64
+    }
+
+    /// The values of the LEDs of the graph, from the first pin given.
+    pub fn leds(&self) -> Vec<f64> {
+        self.board.value()
+    }
+
+    /// Returns the LED at `index`, in the order of the pins given when creating the graph.
+    pub fn led(&self, index: usize) -> Option<&BoardLED> {
+        self.board.led(index)
+    }
+}
+
+impl CompositeDevices for LEDBarGraph {
+    fn close(self) {
+        drop(self)
+    }
+
+    fn is_active(&self) -> bool {
+        self.board.is_active()
+    }
+
+    fn len(&self) -> usize {
+        self.board.len()
+    }
+}
+
+/// A phase of the traffic light sequence, as used in the UK.
+/// `TrafficLights::advance` cycles through the phases in the order
+/// `Stop`, `Ready`, `Go`, `Caution` and back to `Stop`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrafficPhase {
+    /// Only the red light is lit
+    Stop,
+    /// The red and amber lights are lit
+    Ready,
+    /// Only the green light is lit
+    Go,
+    /// Only the amber light is lit
+    Caution,
+}
+
+impl TrafficPhase {
+    /// The `(red, amber, green)` values of the lights for this phase
+    pub fn value(self) -> (f64, f64, f64) {
+        match self {
+            TrafficPhase::Stop => (1.0, 0.0, 0.0),
+            TrafficPhase::Ready => (1.0, 1.0, 0.0),
+            TrafficPhase::Go => (0.0, 0.0, 1.0),
+            TrafficPhase::Caution => (0.0, 1.0, 0.0),
+        }
+    }
+
+    /// The phase following this one in the sequence
+    pub fn next(self) -> TrafficPhase {
+        match self {
+            TrafficPhase::Stop => TrafficPhase::Ready,
+            TrafficPhase::Ready => TrafficPhase::Go,
+            TrafficPhase::Go => TrafficPhase::Caution,
+            TrafficPhase::Caution => TrafficPhase::Stop,
+        }
+    }
+}
+
+const RED: usize = 0;
+const AMBER: usize = 1;
+const GREEN: usize = 2;
+
+/// Represents a set of traffic lights with red, amber and green LEDs,
+/// built on an `LEDBoard` with LEDs named "red", "amber" (or "yellow") and "green".
+///
+/// # Example
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut lights = TrafficLights::new(17, 18, 19);
+/// lights.set_phase(TrafficPhase::Stop).unwrap();
+/// assert_eq!(lights.advance().unwrap(), TrafficPhase::Ready);
+/// assert_eq!(lights.value(), (1.0, 1.0, 0.0));
+/// assert_eq!(factory.get(18).value(), 1.0);
+///
+/// lights.yellow_mut().off().unwrap();
+/// assert_eq!(lights.phase(), Some(TrafficPhase::Stop));
+/// ```
+#[derive(Debug)]
+pub struct TrafficLights {
+    board: LEDBoard,
+}
+
+impl TrafficLights {
+    /// Returns TrafficLights with an `LED` for each of the pin numbers given
+    ///
+    /// * `red` - The GPIO pin that the red LED is attached to
+    /// * `amber` - The GPIO pin that the amber (or yellow) LED is attached to
+    /// * `green` - The GPIO pin that the green LED is attached to
+    pub fn new(red: u8, amber: u8, green: u8) -> TrafficLights {
+        match TrafficLights::try_new(red, amber, green) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new(red: u8, amber: u8, green: u8) -> Result<TrafficLights> {
+        Ok(TrafficLights::with_board(LEDBoard::try_new_named(&[
+            ("red", red),
+            ("amber", amber),
+            ("green", green),
+        ])?))
+    }
+
+    /// Returns TrafficLights with a `PWMLED` for each of the pin numbers given
+    ///
+    /// * `red` - The GPIO pin that the red LED is attached to
+    /// * `amber` - The GPIO pin that the amber (or yellow) LED is attached to
+    /// * `green` - The GPIO pin that the green LED is attached to
+    pub fn new_with_pwm(red: u8, amber: u8, green: u8) -> TrafficLights {
+        match TrafficLights::try_new_with_pwm(red, amber, green) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_with_pwm`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new_with_pwm(red: u8, amber: u8, green: u8) -> Result<TrafficLights> {
+        Ok(TrafficLights::with_board(LEDBoard::try_new_named_with_pwm(
+            &[("red", red), ("amber", amber), ("green", green)],
+        )?))
+    }
+
+    fn with_board(mut board: LEDBoard) -> TrafficLights {
+        board.names.insert("yellow".to_string(), AMBER);
+        TrafficLights { board }
+    }
+
+    /// The LED board holding the lights, to access them by name
+    pub fn board(&self) -> &LEDBoard {
+        &self.board
+    }
+
+    /// The LED board holding the lights, allowing to modify them
+    pub fn board_mut(&mut self) -> &mut LEDBoard {
+        &mut self.board
+    }
+
+    /// The red LED
+    pub fn red(&self) -> &BoardLED {
+        &self.board.leds[RED]
+    }
+
+    /// The red LED, allowing to control it
+    pub fn red_mut(&mut self) -> &mut BoardLED {
+        &mut self.board.leds[RED]
+    }
+
+    /// The amber LED
+    pub fn amber(&self) -> &BoardLED {
+        &self.board.leds[AMBER]
+    }
+
+    /// The amber LED, allowing to control it
+    pub fn amber_mut(&mut self) -> &mut BoardLED {
+        &mut self.board.leds[AMBER]
+    }
+
+    /// The yellow LED, an alias of the amber LED
+    pub fn yellow(&self) -> &BoardLED {
+        self.amber()
+    }
+
+    /// The yellow LED, an alias of the amber LED, allowing to control it
+    pub fn yellow_mut(&mut self) -> &mut BoardLED {
+        self.amber_mut()
+    }
+
+    /// The green LED
+    pub fn green(&self) -> &BoardLED {
+        &self.board.leds[GREEN]
+    }
+
+    /// The green LED, allowing to control it
+    pub fn green_mut(&mut self) -> &mut BoardLED {
+        &mut self.board.leds[GREEN]
+    }
+
+    /// The values of the lights as a `(red, amber, green)` tuple
+    pub fn value(&self) -> (f64, f64, f64) {
+        (
+            self.red().value(),
+            self.amber().value(),
+            self.green().value(),
+        )
+    }
+
+    /// Set the values of the lights from a `(red, amber, green)` tuple
+    pub fn set_value(&mut self, value: (f64, f64, f64)) -> Result<()> {
+        self.board.set_value(&[value.0, value.1, value.2])
+    }
+
+    /// The phase of the sequence shown by the lights, if any
+    pub fn phase(&self) -> Option<TrafficPhase> {
+        let value = self.value();
+        [
+            TrafficPhase::Stop,
+            TrafficPhase::Ready,
+            TrafficPhase::Go,
+            TrafficPhase::Caution,
+        ]
+        .iter()
+        .copied()
+        .find(|phase| phase.value() == value)
+    }
+
+    /// Show the given phase of the sequence
+    pub fn set_phase(&mut self, phase: TrafficPhase) -> Result<()> {
+        self.set_value(phase.value())
+    }
+
+    /// Show the phase following the current one in the sequence, and return it.
+    /// Starts the sequence at `TrafficPhase::Stop` if the lights don't show any phase.
+    pub fn advance(&mut self) -> Result<TrafficPhase> {
+        let phase = match self.phase() {
+            Some(phase) => phase.next(),
+            None => TrafficPhase::Stop,
+        };
+        self.set_phase(phase)?;
+        Ok(phase)
+    }
+
+    /// Turn all the lights on.
+    pub fn on(&mut self) -> Result<()> {
+        self.board.on()
+    }
+
+    /// Turn all the lights off.
+    pub fn off(&mut self) -> Result<()> {
+        self.board.off()
+    }
+
+    /// Toggle all the lights. For each light, if it's on, turn it off; if it's off, turn it on.
+    pub fn toggle(&mut self) -> Result<()> {
+        self.board.toggle()
+    }
+
+    /// Set the number of times to blink the lights
+    /// * `n` - Number of times to blink
+    pub fn set_blink_count(&mut self, n: i32) {
+        self.board.set_blink_count(n)
+    }
+
+    /// Make all the lights turn on and off repeatedly in the background.
+    /// Returns `Error::Unsupported` if fade times are given without PWM.
+    /// * `on_time` - Number of seconds on
+    /// * `off_time` - Number of seconds off
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    pub fn blink(
+        &mut self,
+        on_time: f32,
+        off_time: f32,
+        fade_in_time: f32,
+        fade_out_time: f32,
+    ) -> Result<()> {
+        self.board
+            .blink(on_time, off_time, fade_in_time, fade_out_time)
+    }
+
+    /// Make all the lights fade in and out repeatedly in the background.
+    /// Returns `Error::Unsupported` without PWM.
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    pub fn pulse(&mut self, fade_in_time: f32, fade_out_time: f32) -> Result<()> {
+        self.board.pulse(fade_in_time, fade_out_time)
+    }
+
+    /// Block until all the lights have finished blinking
+    pub fn wait(&mut self) {
+        self.board.wait()
+    }
+}
+
+impl CompositeDevices for TrafficLights {
+    fn close(self) {
+        drop(self)
+    }
+
+    fn is_active(&self) -> bool {
+        self.board.is_active()
+    }
+
+    fn len(&self) -> usize {
+        self.board.len()
+    }
+}
+
+/// Represents a generic dual-motor robot, with a `Motor` on each side.
+/// The speed of each motor is between -1.0 (full speed backward) and 1.0 (full speed forward).
+///
+/// # Example
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut robot = Robot::new((4, 14), (17, 18));
+/// robot.forward(1.0, 0.5, 0.0).unwrap();
+/// assert_eq!(robot.value(), (0.5, 1.0));
+/// assert_eq!(factory.get(4).value(), 0.5);
+///
+/// robot.reverse().unwrap();
+/// assert_eq!(robot.value(), (-0.5, -1.0));
+/// assert_eq!(factory.get(18).value(), 1.0);
+/// ```
+#[derive(Debug)]
+pub struct Robot {
+    left: Motor,
+    right: Motor,
+    source: Option<SourcePump>,
+    source_delay: f32,
+}
+
+impl Robot {
+    /// Returns a Robot with the pin numbers given
+    ///
+    /// * `left` - The `(forward, backward)` GPIO pins of the left motor's driver
+    /// * `right` - The `(forward, backward)` GPIO pins of the right motor's driver
+    pub fn new(left: (u8, u8), right: (u8, u8)) -> Robot {
+        match Robot::try_new(left, right) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new(left: (u8, u8), right: (u8, u8)) -> Result<Robot> {
+        Ok(Robot::from_motors(
+            Motor::try_new(left.0, left.1)?,
+            Motor::try_new(right.0, right.1)?,
+        ))
+    }
+
+    /// Returns a Robot with a `PhaseEnableMotor` on each side, with the pin numbers given
+    ///
+    /// * `left` - The `(phase, enable)` GPIO pins of the left motor's driver
+    /// * `right` - The `(phase, enable)` GPIO pins of the right motor's driver
+    pub fn new_phase_enable(left: (u8, u8), right: (u8, u8)) -> Robot {
+        match Robot::try_new_phase_enable(left, right) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_phase_enable`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new_phase_enable(left: (u8, u8), right: (u8, u8)) -> Result<Robot> {
+        Ok(Robot::from_motors(
+            PhaseEnableMotor::try_new(left.0, left.1)?,
+            PhaseEnableMotor::try_new(right.0, right.1)?,
+        ))
+    }
+
+    /// Returns a Robot driving the given motors, which can be `Motor`s or `PhaseEnableMotor`s
+    /// (e.g. to use motor drivers with an enable or standby pin)
+    pub fn from_motors<L, R>(left: L, right: R) -> Robot
+    where
+        L: Into<Motor>,
+        R: Into<Motor>,
+    {
+        Robot {
+            left: left.into(),
+            right: right.into(),
+            source: None,
+            source_delay: DEFAULT_SOURCE_DELAY,
+        }
+    }
+
+    /// The left motor
+    pub fn left_motor(&mut self) -> &mut Motor {
+        &mut self.left
+    }
+
+    /// The right motor
+    pub fn right_motor(&mut self) -> &mut Motor {
+        &mut self.right
+    }
+
+    /// The speeds of the motors as a `(left, right)` tuple
+    pub fn value(&self) -> (f64, f64) {
+        (self.left.value(), self.right.value())
+    }
+
+    /// Set the speeds of the motors from a `(left, right)` tuple.
+    /// Returns `Error::OutOfRange` if a speed is not between -1.0 and 1.0.
+    /// Stops feeding values from the source set with `set_source`.
+    pub fn set_value(&mut self, value: (f64, f64)) -> Result<()> {
+        for &speed in &[value.0, value.1] {
+            if !(-1.0..=1.0).contains(&speed) {
+                return Err(Error::OutOfRange {
+                    value: speed,
+                    min: -1.0,
+                    max: 1.0,
+                });
+            }
+        }
+        self.clear_source();
+        self.left.set_value(value.0)?;
+        self.right.set_value(value.1)
+    }
+
+    /// Returns the `(left, right)` speeds for driving at `speed` while curving,
+    /// checking that the curves are valid
+    fn curved(speed: f64, curve_left: f64, curve_right: f64) -> Result<(f64, f64)> {
+        for &value in &[speed, curve_left, curve_right] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(Error::OutOfRange {
+                    value,
+                    min: 0.0,
+                    max: 1.0,
+                });
+            }
+        }
+        if curve_left != 0.0 && curve_right != 0.0 {
+            return Err(Error::Unsupported("curving both left and right"));
+        }
+        Ok((speed * (1.0 - curve_left), speed * (1.0 - curve_right)))
+    }
+
+    /// Drive the robot forward by running both motors forward.
+    /// * `speed` - Speed at which to drive the motors, between 0.0 and 1.0
+    /// * `curve_left` - The amount to curve left while moving forwards, by driving the left motor
+    ///   at a slower speed. Between 0.0 (no curve) and 1.0 (left motor stopped)
+    /// * `curve_right` - The amount to curve right while moving forwards, by driving the right
+    ///   motor at a slower speed. `curve_left` and `curve_right` can't both be non-zero
+    pub fn forward(&mut self, speed: f64, curve_left: f64, curve_right: f64) -> Result<()> {
+        let (left, right) = Robot::curved(speed, curve_left, curve_right)?;
+        self.set_value((left, right))
+    }
+
+    /// Drive the robot backward by running both motors backward.
+    /// * `speed` - Speed at which to drive the motors, between 0.0 and 1.0
+    /// * `curve_left` - The amount to curve left while moving backwards, by driving the left
+    ///   motor at a slower speed. Between 0.0 (no curve) and 1.0 (left motor stopped)
+    /// * `curve_right` - The amount to curve right while moving backwards, by driving the right
+    ///   motor at a slower speed. `curve_left` and `curve_right` can't both be non-zero
+    pub fn backward(&mut self, speed: f64, curve_left: f64, curve_right: f64) -> Result<()> {
+        let (left, right) = Robot::curved(speed, curve_left, curve_right)?;
+        self.set_value((-left, -right))
+    }
+
+    /// Make the robot turn left by running the right motor forward and left motor backward.
+    /// * `speed` - Speed at which to drive the motors, between 0.0 and 1.0, otherwise
+    ///   `Error::OutOfRange` is returned
+    pub fn left(&mut self, speed: f64) -> Result<()> {
+        let (speed, _) = Robot::curved(speed, 0.0, 0.0)?;
+        self.set_value((-speed, speed))
+    }
+
+    /// Make the robot turn right by running the left motor forward and right motor backward.
+    /// * `speed` - Speed at which to drive the motors, between 0.0 and 1.0, otherwise
+    ///   `Error::OutOfRange` is returned
+    pub fn right(&mut self, speed: f64) -> Result<()> {
+        let (speed, _) = Robot::curved(speed, 0.0, 0.0)?;
+        self.set_value((speed, -speed))
+    }
+
+    /// Reverse the robot's current motor directions. If the robot is currently running full
+    /// speed forward, it will run full speed backward. If the robot is turning left at
+    /// half-speed, it will turn right at half-speed. If the robot is currently stopped it
+    /// will remain stopped.
+    pub fn reverse(&mut self) -> Result<()> {
+        let (left, right) = self.value();
+        self.set_value((-left, -right))
+    }
+
+    /// Stop the robot, and stop feeding values from the source set with `set_source`.
+    pub fn stop(&mut self) -> Result<()> {
+        self.clear_source();
+        self.left.stop()?;
+        self.right.stop()
+    }
+
+    /// Continuously feed the `(left, right)` speeds of `source` (e.g. the position of a joystick)
+    /// into the motors from a background thread, until the source is exhausted or
+    /// `clear_source` is called. Replaces any previously set source.
+    pub fn set_source<I>(&mut self, source: I)
+    where
+        I: IntoIterator<Item = (f64, f64)>,
+        I::IntoIter: Send + 'static,
+    {
+        self.clear_source();
+        let mut left = self.left.source_writer();
+        let mut right = self.right.source_writer();
+        self.source = Some(SourcePump::start(
+            source,
+            self.source_delay,
+            move |(left_value, right_value)| {
+                left(left_value)?;
+                right(right_value)
+            },
+        ));
+    }
+
+    /// Stop feeding values from the source set with `set_source`.
+    pub fn clear_source(&mut self) {
+        if let Some(mut source) = self.source.take() {
+            source.stop();
+        }
+    }
+
+    /// The delay in seconds between two values read from the source. Defaults to 0.01.
+    pub fn source_delay(&self) -> f32 {
+        self.source_delay
+    }
+
+    /// Set the delay in seconds between two values read from the source.
+    /// Takes effect the next time `set_source` is called.
+    /// Returns `Error::OutOfRange` if the delay is negative or infinite.
+    pub fn set_source_delay(&mut self, seconds: f32) -> Result<()> {
+        check_delay(seconds)?;
+        self.source_delay = seconds;
+        Ok(())
+    }
+}
+
+impl Drop for Robot {
+    /// Stops any source before the motors are dropped, so that it can't drive them again.
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+impl CompositeDevices for Robot {
+    fn close(self) {
+        drop(self)
+    }
+
+    fn is_active(&self) -> bool {
+        self.value() != (0.0, 0.0)
+    }
+
+    fn len(&self) -> usize {
+        2
+    }
+}
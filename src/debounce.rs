@@ -3,8 +3,11 @@ use std::ops::{Deref, DerefMut};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use crate::error::Result;
+use crate::gestures::Gesture;
+use crate::input_devices::{poll_interrupt, CallbackHandle, Event};
+use crate::pins::Trigger;
 use crate::Button;
-use rppal::gpio::{self, Level, Trigger};
 
 /// Adds `.debounce()` method to [`Button`] for converting to a [`Debounced`] button
 pub trait Debounce {
@@ -13,19 +16,57 @@ pub trait Debounce {
 
 impl Debounce for Button {
     fn debounce(self, duration: Duration) -> Debounced {
+        let state = Arc::new(Mutex::new(DebounceState::default()));
+        // The press statistics see the same changes as the callbacks
+        let filter = Arc::clone(&state);
+        self.filter_presses(Box::new(move |active, timestamp| {
+            filter.lock().unwrap().accept(timestamp, active, duration)
+        }));
         Debounced {
             inner: self,
             period: duration,
-            last_trigger: Arc::new(Mutex::new(None)),
+            state,
         }
     }
 }
 
+/// Debouncing state shared by the callbacks of a [`Debounced`] button
+#[derive(Debug, Default)]
+struct DebounceState {
+    /// When the last change was accepted
+    last_trigger: Option<Instant>,
+    /// The last change seen (timestamp and state) and whether it was accepted. Every callback
+    /// sees each change, and must reach the same decision as the first one did.
+    current: Option<(Instant, bool, bool)>,
+}
+
+impl DebounceState {
+    /// Returns `true` if the change to `active` at `timestamp` is outside the debounce period
+    /// of the last accepted change
+    fn accept(&mut self, timestamp: Instant, active: bool, period: Duration) -> bool {
+        if let Some((last, last_active, accepted)) = self.current {
+            if last == timestamp && last_active == active {
+                return accepted;
+            }
+        }
+        let accepted = match self.last_trigger {
+            Some(last) => timestamp.saturating_duration_since(last) >= period,
+            // No previous change, so the debounce period doesn't matter
+            None => true,
+        };
+        if accepted {
+            self.last_trigger = Some(timestamp);
+        }
+        self.current = Some((timestamp, active, accepted));
+        accepted
+    }
+}
+
 /// Wrapper type for [`Button`] to allow for software [debouncing](https://en.wikipedia.org/wiki/Switch#Contact%20Bounce). Will prevent
 /// Subsequent triggers with the given debounce period (E.g. 50-100 milliseconds)
 ///
 /// Can be used with blocking functions (E.g [`Button::wait_for_press`]):
-/// ```
+/// ```no_run
 /// use rust_gpiozero::{Button, Debounce};
 /// use std::time::Duration;
 ///
@@ -39,7 +80,7 @@ impl Debounce for Button {
 /// ```
 ///
 /// Or async interrupt functions (E.g. [`Button::when_pressed`]):
-/// ```
+/// ```no_run
 /// use rust_gpiozero::{Button, Debounce};
 /// use std::time::Duration;
 ///
@@ -53,11 +94,14 @@ impl Debounce for Button {
 ///     println!("button pressed");
 /// }).unwrap();
 /// ```
-
+///
+/// Hold detection ([`Button::when_held`], [`Button::is_held`]) restarts its timer on every
+/// bounce, so a hold is only reported once the contact has settled for the hold time. The
+/// press statistics ([`Button::set_press_stats`]) ignore the bounces, like the callbacks.
 pub struct Debounced {
     inner: Button,
     period: Duration,
-    last_trigger: Arc<Mutex<Option<Instant>>>,
+    state: Arc<Mutex<DebounceState>>,
 }
 
 impl fmt::Debug for Debounced {
@@ -88,11 +132,16 @@ impl Debounced {
             false => Trigger::FallingEdge,
         };
         let timeout = timeout.map(|seconds| Duration::from_millis((seconds * 1000.0) as u64));
-        self.inner.pin.set_interrupt(trigger).unwrap();
+        self.inner
+            .pin
+            .lock()
+            .unwrap()
+            .set_interrupt(trigger)
+            .unwrap();
         loop {
-            self.inner.pin.poll_interrupt(true, timeout).unwrap();
+            poll_interrupt(&self.inner.pin, true, timeout).unwrap();
             // Check that enough time has passed since the last press
-            if let Some(last_trigger) = self.last_trigger.lock().unwrap().as_ref() {
+            if let Some(last_trigger) = self.state.lock().unwrap().last_trigger.as_ref() {
                 // If this press is within the debounce time, continue blocking until the next press
                 if last_trigger.elapsed() < self.period {
                     continue;
@@ -101,42 +150,69 @@ impl Debounced {
             // if self.last_trigger is not set, there have been no previous presses so debounce time doesn't matter
             break;
         }
-        self.last_trigger.lock().unwrap().replace(Instant::now());
+        self.state.lock().unwrap().last_trigger = Some(Instant::now());
+        self.inner.restore_callbacks().unwrap();
     }
 
-    /// Asynchronously invokes the passed closure everytime the button is pressed, if the debounce period has passed
-    pub fn when_pressed<C>(&mut self, action: C) -> Result<(), gpio::Error>
+    /// Asynchronously invokes the passed closure everytime the button is pressed, if the debounce period has passed.
+    /// Returns a handle to remove this callback without affecting the others.
+    pub fn when_pressed<C>(&mut self, action: C) -> Result<CallbackHandle>
     where
-        C: FnMut(Level) + Send + 'static,
+        C: FnMut(Event) + Send + 'static,
     {
         self.action_on(true, action)
     }
 
-    /// Asynchronously invokes the passed closure everytime the button is released, if the debounce period has passed
-    pub fn when_released<C>(&mut self, action: C) -> Result<(), gpio::Error>
+    /// Asynchronously invokes the passed closure everytime the button is released, if the debounce period has passed.
+    /// Returns a handle to remove this callback without affecting the others.
+    pub fn when_released<C>(&mut self, action: C) -> Result<CallbackHandle>
     where
-        C: FnMut(Level) + Send + 'static,
+        C: FnMut(Event) + Send + 'static,
     {
         self.action_on(false, action)
     }
 
-    pub(crate) fn action_on<C>(&mut self, active: bool, mut action: C) -> Result<(), gpio::Error>
+    /// Asynchronously invokes the passed closure with the gestures made with the button,
+    /// ignoring the presses and releases within the debounce period. See [`Button::when_gesture`].
+    pub fn when_gesture<C>(&mut self, callback: C) -> Result<CallbackHandle>
+    where
+        C: FnMut(Gesture) + Send + 'static,
+    {
+        let recognizer = self.inner.gesture_recognizer(callback);
+        let action = self.debounced(recognizer);
+        self.inner.when_changed(action)
+    }
+
+    pub(crate) fn action_on<C>(&mut self, active: bool, mut action: C) -> Result<CallbackHandle>
+    where
+        C: FnMut(Event) + Send + 'static,
+    {
+        // Subscribed to every change, so that releases debounce presses and vice versa
+        let action = self.debounced(move |event| {
+            if event.active == active {
+                action(event);
+            }
+        });
+        self.inner.when_changed(action)
+    }
+
+    /// Wraps `action`, to be subscribed to every change of the button, so that it isn't
+    /// executed within the debounce period
+    fn debounced<C>(&self, mut action: C) -> impl FnMut(Event) + Send + 'static
     where
-        C: FnMut(Level) + Send + 'static,
+        C: FnMut(Event) + Send + 'static,
     {
         let period = self.period;
-        let last_trigger = self.last_trigger.clone();
-        self.inner.action_on(active, move |level| {
-            let mut lt = last_trigger.lock().unwrap();
-            if let Some(last) = lt.as_ref() {
-                if last.elapsed() < period {
-                    // Within debounce period, don't execute action
-                    return;
-                }
+        let state = self.state.clone();
+        move |event| {
+            let accepted = state
+                .lock()
+                .unwrap()
+                .accept(event.timestamp, event.active, period);
+            if accepted {
+                action(event);
             }
-            lt.replace(Instant::now());
-            action(level);
-        })
+        }
     }
 }
 
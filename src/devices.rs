@@ -1,6 +1,7 @@
 //! Describes generic devices such as `GPIODevice` and `CompositeDevice`
 
-use rppal::gpio::{Gpio, Level, Pin};
+use crate::error::Result;
+use crate::pins::{default_factory, Level, Pin};
 
 /// Represents a single device of any type; GPIO-based, SPI-based, I2C-based,
 /// etc.  It defines the basic services applicable to all devices
@@ -29,7 +30,7 @@ macro_rules! impl_device {
 /// Represents a generic GPIO device and provides the services common to all single-pin GPIO devices
 #[derive(Debug)]
 pub struct GpioDevice {
-    pin: Pin,
+    pin: Box<dyn Pin>,
     active_state: bool,
     inactive_state: bool,
 }
@@ -49,19 +50,21 @@ impl GpioDevice {
     ///
     /// * `pin` - The GPIO pin which the device is attached to
     pub fn new(pin: u8) -> GpioDevice {
-        match Gpio::new() {
+        match GpioDevice::try_new(pin) {
             Err(e) => panic!("{:?}", e),
-            Ok(gpio) => match gpio.get(pin) {
-                Err(e) => panic!("{:?}", e),
-                Ok(pin) => GpioDevice {
-                    pin,
-                    active_state: true,
-                    inactive_state: false,
-                },
-            },
+            Ok(device) => device,
         }
     }
 
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<GpioDevice> {
+        Ok(GpioDevice {
+            pin: default_factory().pin(pin)?,
+            active_state: true,
+            inactive_state: false,
+        })
+    }
+
     /// Returns a value representing the device's state.
     pub fn value(&self) -> bool {
         self.state_to_value()
@@ -70,10 +73,28 @@ impl GpioDevice {
     fn state_to_value(&self) -> bool {
         match self.pin.read() {
             Level::High => self.active_state,
-            Level::Low => !self.active_state,
+            Level::Low => self.inactive_state,
         }
     }
 
     impl_device!();
     impl_gpio_device!();
 }
+
+/// Represents a device composed of multiple devices like simple HATs,
+/// H-bridge motor controllers, robots composed of multiple motors, etc.
+pub trait CompositeDevices {
+    /// Shut down the device and release all associated resources.
+    fn close(self);
+
+    /// Returns ``True`` if any of the contained devices is currently active and ``False`` otherwise.
+    fn is_active(&self) -> bool;
+
+    /// Returns the number of devices contained.
+    fn len(&self) -> usize;
+
+    /// Returns ``True`` if the composite device doesn't contain any device.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
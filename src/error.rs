@@ -0,0 +1,133 @@
+//! Error type shared by the devices and pin backends of this crate
+use rppal::gpio;
+use rppal::pwm;
+use std::error;
+use std::fmt;
+use std::io;
+use std::result;
+
+/// Errors that can occur when accessing a GPIO device
+///
+/// # Example : Recovering from a busy pin
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::{Error, LED};
+///
+/// pins::set_default_factory(MockFactory::new());
+///
+/// let led = LED::try_new(17).unwrap();
+/// match LED::try_new(17) {
+///     Err(Error::PinBusy(pin)) => println!("pin {} is already in use", pin),
+///     _ => unreachable!(),
+/// }
+///
+/// // The pin can be used again once the first device is dropped
+/// drop(led);
+/// assert!(LED::try_new(17).is_ok());
+/// ```
+#[derive(Debug)]
+pub enum Error {
+    /// The pin is already in use elsewhere in the application.
+    PinBusy(u8),
+    /// The GPIO peripheral doesn't expose a pin with this number.
+    UnknownPin(u8),
+    /// Permission denied when opening the GPIO device at the given path.
+    PermissionDenied(String),
+    /// The board could not be identified, or isn't supported by the pin backend.
+    UnsupportedBoard,
+    /// The pin backend doesn't support the named operation.
+    Unsupported(&'static str),
+    /// A value is outside the range accepted by the device.
+    OutOfRange {
+        /// The rejected value
+        value: f64,
+        /// The smallest accepted value
+        min: f64,
+        /// The largest accepted value
+        max: f64,
+    },
+    /// A composite device was given a different number of values than it has devices.
+    WrongValueCount {
+        /// The number of devices
+        expected: usize,
+        /// The number of values given
+        actual: usize,
+    },
+    /// A servo pulse width (in microseconds) conflicts with the servo's other timings.
+    InvalidPulseWidth(u64),
+    /// Two devices of a composite device were given the same name.
+    DuplicateName(String),
+    /// A tone couldn't be parsed from a note name, MIDI note number or frequency.
+    InvalidTone(String),
+    /// Any other error reported by the [rppal](https://github.com/golemparts/rppal) backend.
+    Gpio(gpio::Error),
+    /// An I/O error reported by a pin backend.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::PinBusy(pin) => write!(f, "Pin {} is already in use", pin),
+            Error::UnknownPin(pin) => write!(f, "Pin {} does not exist", pin),
+            Error::PermissionDenied(ref path) => write!(f, "Permission denied: {}", path),
+            Error::UnsupportedBoard => write!(f, "Unsupported board"),
+            Error::Unsupported(operation) => {
+                write!(f, "{} is not supported by the pin backend", operation)
+            }
+            Error::OutOfRange { value, min, max } => write!(
+                f,
+                "Value {} is out of range, must be between {} and {}",
+                value, min, max
+            ),
+            Error::WrongValueCount { expected, actual } => write!(
+                f,
+                "Expected {} values, one for each device, but got {}",
+                expected, actual
+            ),
+            Error::InvalidPulseWidth(width) => write!(f, "Invalid pulse width: {}us", width),
+            Error::DuplicateName(ref name) => write!(f, "Duplicate device name: {}", name),
+            Error::InvalidTone(ref tone) => write!(f, "Invalid tone: {}", tone),
+            Error::Gpio(ref err) => write!(f, "GPIO error: {}", err),
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Gpio(ref err) => Some(err),
+            Error::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<gpio::Error> for Error {
+    fn from(err: gpio::Error) -> Error {
+        match err {
+            gpio::Error::UnknownModel => Error::UnsupportedBoard,
+            gpio::Error::PermissionDenied(path) => Error::PermissionDenied(path),
+            err => Error::Gpio(err),
+        }
+    }
+}
+
+impl From<pwm::Error> for Error {
+    fn from(err: pwm::Error) -> Error {
+        match err {
+            pwm::Error::Io(err) => Error::Io(err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+/// Result type returned from methods that can have `rust_gpiozero::Error`s.
+pub type Result<T> = result::Result<T, Error>;
@@ -0,0 +1,204 @@
+//! Fade engine used by blinking, beeping and pulsing devices
+use crate::error::Result;
+use crate::scheduler::{self, Task};
+use std::f64::consts::PI;
+use std::time::{Duration, Instant};
+
+/// Curve mapping the progress of a fade to the brightness of a device.
+///
+/// Fades progress linearly from off to on (or back), and the easing curve turns that progress
+/// into the value written to the device. Fading in and out use the same curve, so fades are
+/// symmetric.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Easing {
+    /// The value changes at a constant rate
+    #[default]
+    Linear,
+    /// The value follows the square of the progress, changing slowly near off
+    Quadratic,
+    /// The value follows a half sine wave, changing slowly near off and near on
+    Sine,
+    /// The value is the progress raised to the given power. LEDs appear to fade at a
+    /// constant rate to the human eye with a gamma of about 2.2.
+    Gamma(f64),
+}
+
+impl Easing {
+    /// Returns the value for the given progress, both between 0.0 and 1.0
+    pub fn apply(self, progress: f64) -> f64 {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => progress,
+            Easing::Quadratic => progress * progress,
+            Easing::Sine => (1.0 - (PI * progress).cos()) / 2.0,
+            Easing::Gamma(gamma) => progress.powf(gamma),
+        }
+    }
+}
+
+/// Default number of value changes per second while fading
+pub(crate) const DEFAULT_FADE_FPS: f32 = 25.0;
+
+/// A step of a blink cycle: a fade from one level to another, or a constant level
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Segment {
+    from: f64,
+    to: f64,
+    duration: Duration,
+}
+
+/// Returns the cycle of a blink: fading in, staying on, fading out and staying off.
+/// Negative times are treated as 0, and infinite ones as very long times.
+pub(crate) fn blink_cycle(
+    on_time: f32,
+    off_time: f32,
+    fade_in_time: f32,
+    fade_out_time: f32,
+) -> Vec<Segment> {
+    let seconds = scheduler::saturating_seconds;
+    let mut cycle = Vec::new();
+    if fade_in_time > 0.0 {
+        cycle.push(Segment {
+            from: 0.0,
+            to: 1.0,
+            duration: seconds(fade_in_time),
+        });
+    }
+    cycle.push(Segment {
+        from: 1.0,
+        to: 1.0,
+        duration: seconds(on_time),
+    });
+    if fade_out_time > 0.0 {
+        cycle.push(Segment {
+            from: 1.0,
+            to: 0.0,
+            duration: seconds(fade_out_time),
+        });
+    }
+    cycle.push(Segment {
+        from: 0.0,
+        to: 0.0,
+        duration: seconds(off_time),
+    });
+    cycle
+}
+
+/// Settings of the fades of a blinking or pulsing device
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Fader {
+    pub(crate) fps: f32,
+    pub(crate) easing: Easing,
+}
+
+impl Default for Fader {
+    fn default() -> Fader {
+        Fader {
+            fps: DEFAULT_FADE_FPS,
+            easing: Easing::default(),
+        }
+    }
+}
+
+impl Fader {
+    /// Number of frames of a segment: one for a constant level, or enough for the frame rate
+    fn frames(&self, segment: &Segment) -> u32 {
+        if segment.from == segment.to {
+            1
+        } else {
+            ((segment.duration.as_secs_f64() * f64::from(self.fps)).round() as u32).max(1)
+        }
+    }
+
+    /// Returns a task running `cycle` `n` times, or endlessly if `n` is `None`, writing the
+    /// eased level of each frame.
+    ///
+    /// Endless blinks join the cycle at the point reached by all endless blinks with the same
+    /// period since the scheduler epoch, so that they stay in lock-step. A cycle without any
+    /// duration is only run once.
+    pub(crate) fn blink<F>(&self, cycle: Vec<Segment>, n: Option<i32>, write: F) -> Blink<F>
+    where
+        F: FnMut(f64) -> Result<()> + Send,
+    {
+        let now = Instant::now();
+        let period: Duration = cycle.iter().map(|segment| segment.duration).sum();
+        let mut blink = Blink {
+            fader: *self,
+            cycle,
+            n,
+            write,
+            start: now,
+            segment: 0,
+            frame: 0,
+            count: 0,
+        };
+        if period.is_zero() {
+            blink.n = Some(n.unwrap_or(1));
+        } else if n.is_none() {
+            let elapsed = now.duration_since(scheduler::epoch());
+            let offset = elapsed.as_nanos() % period.as_nanos();
+            blink.start = now - Duration::from_nanos(offset as u64);
+            while blink.deadline() <= now {
+                blink.advance();
+            }
+        }
+        blink
+    }
+}
+
+/// A blink cycle repeated by the scheduler. Frames are due at absolute deadlines from the
+/// start of the cycle, so that time spent writing doesn't accumulate as drift.
+pub(crate) struct Blink<F> {
+    fader: Fader,
+    cycle: Vec<Segment>,
+    n: Option<i32>,
+    write: F,
+    /// Start of the current segment
+    start: Instant,
+    segment: usize,
+    frame: u32,
+    count: i32,
+}
+
+impl<F> Blink<F> {
+    /// When the current frame ends
+    fn deadline(&self) -> Instant {
+        let segment = &self.cycle[self.segment];
+        let frames = self.fader.frames(segment);
+        self.start + segment.duration * (self.frame + 1) / frames
+    }
+
+    /// Moves on to the next frame
+    fn advance(&mut self) {
+        let segment = self.cycle[self.segment];
+        self.frame += 1;
+        if self.frame == self.fader.frames(&segment) {
+            self.frame = 0;
+            self.start += segment.duration;
+            self.segment += 1;
+            if self.segment == self.cycle.len() {
+                self.segment = 0;
+                self.count += 1;
+            }
+        }
+    }
+}
+
+impl<F> Task for Blink<F>
+where
+    F: FnMut(f64) -> Result<()> + Send,
+{
+    /// Writes the level of the current frame. Fails if the write fails.
+    fn step(&mut self) -> Result<Option<Instant>> {
+        if self.cycle.is_empty() || self.n.is_some_and(|n| self.count >= n) {
+            return Ok(None);
+        }
+        let segment = self.cycle[self.segment];
+        let progress = f64::from(self.frame) / f64::from(self.fader.frames(&segment));
+        let level = segment.from + (segment.to - segment.from) * progress;
+        (self.write)(self.fader.easing.apply(level))?;
+        let deadline = self.deadline();
+        self.advance();
+        Ok(Some(deadline))
+    }
+}
@@ -0,0 +1,140 @@
+//! Recognition of clicks and long presses from the presses and releases of a button
+use crate::error::Result;
+use crate::input_devices::Event;
+use crate::scheduler::{self, Task, TaskHandle};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::{Duration, Instant};
+
+/// A gesture made with a button, recognized from its presses and releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    /// The button was clicked the given number of times in a row, each click following the
+    /// previous one within the click timeout. A double click is reported as `Click(2)` only,
+    /// without a `Click(1)` before it.
+    Click(u32),
+    /// The button has been held down for its hold time.
+    LongPress,
+    /// The button was released after a `LongPress`.
+    Release,
+}
+
+type GestureCallback = Box<dyn FnMut(Gesture) + Send>;
+
+struct State {
+    pressed: bool,
+    /// Clicks made in a row so far, not reported yet
+    clicks: u32,
+    long_press: bool,
+    /// Reports the clicks once the click timeout is over, or the long press
+    timer: Option<TaskHandle>,
+    /// Incremented on each press and release, so that a stale timer does nothing
+    generation: u64,
+}
+
+/// Turns the presses and releases of a button into gestures
+pub(crate) struct Recognizer {
+    click_timeout: Duration,
+    long_press_time: Duration,
+    state: Mutex<State>,
+    /// Locked before the state is unlocked, so that the gestures are reported in order
+    callback: Mutex<GestureCallback>,
+}
+
+impl Recognizer {
+    /// Returns a callback for the changes of a button, invoking `callback` with the gestures
+    /// they make.
+    pub(crate) fn callback(
+        click_timeout: Duration,
+        long_press_time: Duration,
+        callback: GestureCallback,
+    ) -> impl FnMut(Event) + Send + 'static {
+        let recognizer = Arc::new(Recognizer {
+            click_timeout,
+            long_press_time,
+            state: Mutex::new(State {
+                pressed: false,
+                clicks: 0,
+                long_press: false,
+                timer: None,
+                generation: 0,
+            }),
+            callback: Mutex::new(callback),
+        });
+        move |event| recognizer.change(event)
+    }
+
+    /// Updates the gesture in progress. Events which don't change the state of the button,
+    /// such as a release dropped by debouncing, are ignored.
+    fn change(self: &Arc<Self>, event: Event) {
+        let mut state = self.state.lock().unwrap();
+        if event.active == state.pressed {
+            return;
+        }
+        state.pressed = event.active;
+        state.generation += 1;
+        if let Some(timer) = state.timer.take() {
+            timer.abort();
+        }
+        if event.active {
+            self.start_timer(&mut state, event.timestamp + self.long_press_time);
+        } else if state.long_press {
+            state.long_press = false;
+            self.report(state, &[Gesture::Release]);
+        } else {
+            state.clicks += 1;
+            self.start_timer(&mut state, event.timestamp + self.click_timeout);
+        }
+    }
+
+    /// Invokes the callback with `gestures` once `state` is unlocked, so that the callback can
+    /// use the button without deadlocking.
+    fn report(&self, state: MutexGuard<State>, gestures: &[Gesture]) {
+        let mut callback = self.callback.lock().unwrap();
+        drop(state);
+        for &gesture in gestures {
+            callback(gesture);
+        }
+    }
+
+    fn start_timer(self: &Arc<Self>, state: &mut State, due: Instant) {
+        state.timer = Some(scheduler::schedule_at(
+            due,
+            GestureTimer {
+                recognizer: Arc::downgrade(self),
+                generation: state.generation,
+            },
+        ));
+    }
+}
+
+/// Reports a long press if the button is still pressed, or the clicks made in a row once
+/// it wasn't pressed again in time
+struct GestureTimer {
+    recognizer: Weak<Recognizer>,
+    generation: u64,
+}
+
+impl Task for GestureTimer {
+    fn step(&mut self) -> Result<Option<Instant>> {
+        let recognizer = match self.recognizer.upgrade() {
+            Some(recognizer) => recognizer,
+            None => return Ok(None),
+        };
+        let mut state = recognizer.state.lock().unwrap();
+        if state.generation != self.generation {
+            return Ok(None);
+        }
+        state.timer = None;
+        let mut gestures = Vec::with_capacity(2);
+        let clicks = std::mem::take(&mut state.clicks);
+        if clicks > 0 {
+            gestures.push(Gesture::Click(clicks));
+        }
+        if state.pressed {
+            state.long_press = true;
+            gestures.push(Gesture::LongPress);
+        }
+        recognizer.report(state, &gestures);
+        Ok(None)
+    }
+}
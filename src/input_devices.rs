@@ -1,13 +1,526 @@
 //! Input device component interfaces for devices such as `Button`
-use rppal::gpio::{self, Gpio, InputPin, Level, Trigger};
-use std::time::Duration;
+use crate::error::{Error, Result};
+use crate::gestures::{Gesture, Recognizer};
+use crate::pins::{default_factory, InputPin, Level, PullUpDown, Trigger};
+use crate::scheduler::{self, Task, TaskHandle};
+use std::fmt;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+/// An input pin shared between a device and the iterators returned by its `values`
+pub(crate) type SharedInputPin = Arc<Mutex<Box<dyn InputPin>>>;
+
+/// The changes of an input device a callback is invoked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EventKind {
+    Activated,
+    Deactivated,
+    Changed,
+    /// Fired by the hold timer rather than by a change of level
+    Held,
+}
+
+impl EventKind {
+    fn matches(self, active: bool) -> bool {
+        match self {
+            EventKind::Activated => active,
+            EventKind::Deactivated => !active,
+            EventKind::Changed => true,
+            EventKind::Held => false,
+        }
+    }
+}
+
+/// A change of state of an input device, passed to its callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    /// `true` if the device was activated, `false` if it was deactivated. This takes the
+    /// pull-up/pull-down setting of the device into account, so a pressed pull-up button is
+    /// active although its pin is low.
+    pub active: bool,
+    /// The GPIO pin of the device.
+    pub pin: u8,
+    /// When the change was detected.
+    pub timestamp: Instant,
+    /// Time elapsed since the previous event of the device, or `None` for its first event.
+    pub since_previous: Option<Duration>,
+}
+
+type Callback = Arc<Mutex<Box<dyn FnMut(Event) + Send>>>;
+
+/// Default time a button must be held for before its `when_held` callbacks are invoked
+const DEFAULT_HOLD_TIME: Duration = Duration::from_secs(1);
+
+/// Default time within which a click must follow the previous one to make a multiple click
+const DEFAULT_CLICK_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// The callbacks registered on an input device. A single async interrupt on the pin
+/// dispatches each change to all the callbacks interested in it.
+pub(crate) struct Callbacks {
+    installed: bool,
+    last_event: Option<Instant>,
+    next_id: u64,
+    entries: Vec<(u64, EventKind, Callback)>,
+    /// When the device was last activated, if it is still active
+    active_since: Option<Instant>,
+    hold_time: Duration,
+    hold_repeat: bool,
+    /// Fires the `Held` callbacks while the device stays active
+    hold_timer: Option<TaskHandle>,
+    /// Statistics of the presses of a button, if enabled
+    stats: Option<PressStats>,
+    /// When the device was last activated as counted by the statistics, if it is still active
+    pressed_since: Option<Instant>,
+    /// Decides which changes count in the statistics, e.g. to ignore contact bounces
+    press_filter: Option<PressFilter>,
+}
+
+/// Returns whether the change of a device to the given state at the given time counts in its
+/// press statistics
+pub(crate) type PressFilter = Box<dyn FnMut(bool, Instant) -> bool + Send>;
+
+/// The callbacks of an input device, shared with the async interrupt of its pin
+pub(crate) type SharedCallbacks = Arc<Mutex<Callbacks>>;
+
+impl Default for Callbacks {
+    fn default() -> Callbacks {
+        Callbacks {
+            installed: false,
+            last_event: None,
+            next_id: 0,
+            entries: Vec::new(),
+            active_since: None,
+            hold_time: DEFAULT_HOLD_TIME,
+            hold_repeat: false,
+            hold_timer: None,
+            stats: None,
+            pressed_since: None,
+            press_filter: None,
+        }
+    }
+}
+
+impl Callbacks {
+    /// Returns the callbacks registered for `kind`
+    fn matching(&self, kind: impl Fn(EventKind) -> bool) -> Vec<Callback> {
+        self.entries
+            .iter()
+            .filter(|(_, entry, _)| kind(*entry))
+            .map(|(_, _, callback)| Arc::clone(callback))
+            .collect()
+    }
+
+    /// Records the activation or deactivation of the device at `timestamp`, starting the hold
+    /// timer if the device was activated and has `Held` callbacks.
+    fn set_active(&mut self, shared: &SharedCallbacks, pin: u8, active: bool, timestamp: Instant) {
+        if let Some(timer) = self.hold_timer.take() {
+            // Doesn't wait, as this may run on the scheduler thread from a held callback
+            timer.abort();
+        }
+        let counted = match &mut self.press_filter {
+            Some(filter) => filter(active, timestamp),
+            None => true,
+        };
+        if counted {
+            if let Some(stats) = &mut self.stats {
+                match (active, self.pressed_since) {
+                    (true, None) => stats.pressed(timestamp),
+                    (false, Some(since)) => stats.released(since, timestamp),
+                    _ => {}
+                }
+            }
+            self.pressed_since = if active { Some(timestamp) } else { None };
+        }
+        self.active_since = if active { Some(timestamp) } else { None };
+        let held = self
+            .entries
+            .iter()
+            .any(|(_, kind, _)| *kind == EventKind::Held);
+        if active && held {
+            self.hold_timer = Some(scheduler::schedule_at(
+                timestamp + self.hold_time,
+                HoldTimer {
+                    callbacks: Arc::downgrade(shared),
+                    pin,
+                    since: timestamp,
+                },
+            ));
+        }
+    }
+}
+
+impl fmt::Debug for Callbacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Callbacks")
+            .field("count", &self.entries.len())
+            .field("hold_time", &self.hold_time)
+            .field("hold_repeat", &self.hold_repeat)
+            .finish()
+    }
+}
+
+/// Statistics of the presses of a button, returned by `Button::press_stats`.
+/// Only the presses made while the async interrupt of the button is installed are counted,
+/// see `Button::set_press_stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PressStats {
+    count: u64,
+    releases: u64,
+    last_pressed: Option<Instant>,
+    last_released: Option<Instant>,
+    last_duration: Option<Duration>,
+    total_duration: Duration,
+    max_duration: Option<Duration>,
+}
+
+impl PressStats {
+    fn pressed(&mut self, timestamp: Instant) {
+        self.count += 1;
+        self.last_pressed = Some(timestamp);
+    }
+
+    fn released(&mut self, pressed: Instant, timestamp: Instant) {
+        let duration = timestamp.saturating_duration_since(pressed);
+        self.releases += 1;
+        self.last_released = Some(timestamp);
+        self.last_duration = Some(duration);
+        self.total_duration += duration;
+        self.max_duration = self.max_duration.max(Some(duration));
+    }
+
+    /// The number of times the button was pressed
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// When the button was last pressed
+    pub fn last_pressed(&self) -> Option<Instant> {
+        self.last_pressed
+    }
+
+    /// When the button was last released
+    pub fn last_released(&self) -> Option<Instant> {
+        self.last_released
+    }
+
+    /// How long the button was held down for the last time it was released
+    pub fn last_duration(&self) -> Option<Duration> {
+        self.last_duration
+    }
+
+    /// The mean time the button was held down for, over all the presses it was released from
+    pub fn mean_duration(&self) -> Option<Duration> {
+        if self.releases == 0 {
+            None
+        } else {
+            Some(self.total_duration.div_f64(self.releases as f64))
+        }
+    }
+
+    /// The longest time the button was held down for
+    pub fn max_duration(&self) -> Option<Duration> {
+        self.max_duration
+    }
+}
+
+/// Invokes the `Held` callbacks of a device once it has been active for its hold time, and
+/// then every hold time if it repeats
+struct HoldTimer {
+    callbacks: Weak<Mutex<Callbacks>>,
+    pin: u8,
+    /// The activation the timer was started for
+    since: Instant,
+}
+
+impl Task for HoldTimer {
+    /// Stops if the device was deactivated or dropped since the timer started.
+    fn step(&mut self) -> Result<Option<Instant>> {
+        let callbacks = match self.callbacks.upgrade() {
+            Some(callbacks) => callbacks,
+            None => return Ok(None),
+        };
+        let timestamp = Instant::now();
+        let (event, matching, next) = {
+            let callbacks = callbacks.lock().unwrap();
+            if callbacks.active_since != Some(self.since) {
+                return Ok(None);
+            }
+            // Holds aren't changes, so they don't count as the previous event of the device
+            let event = Event {
+                active: true,
+                pin: self.pin,
+                timestamp,
+                since_previous: callbacks
+                    .last_event
+                    .map(|last| timestamp.saturating_duration_since(last)),
+            };
+            let next = if callbacks.hold_repeat {
+                Some(timestamp + callbacks.hold_time)
+            } else {
+                None
+            };
+            (
+                event,
+                callbacks.matching(|kind| kind == EventKind::Held),
+                next,
+            )
+        };
+        for callback in matching {
+            (callback.lock().unwrap())(event);
+        }
+        Ok(next)
+    }
+}
+
+/// Installs the async interrupt dispatching the changes of the device to its callbacks,
+/// replacing any other async interrupt on the pin.
+pub(crate) fn install(
+    pin: &Mutex<Box<dyn InputPin>>,
+    callbacks: &SharedCallbacks,
+    active_state: bool,
+) -> Result<()> {
+    let dispatch = Arc::clone(callbacks);
+    let mut pin = pin.lock().unwrap();
+    let number = pin.pin();
+    pin.set_async_interrupt(
+        Trigger::Both,
+        Box::new(move |level| {
+            let timestamp = Instant::now();
+            let event;
+            // Callbacks run without the lock held, so that they can remove themselves
+            let matching: Vec<Callback> = {
+                let mut callbacks = dispatch.lock().unwrap();
+                event = Event {
+                    active: (level == Level::High) == active_state,
+                    pin: number,
+                    timestamp,
+                    since_previous: callbacks
+                        .last_event
+                        .map(|last| timestamp.saturating_duration_since(last)),
+                };
+                callbacks.last_event = Some(timestamp);
+                callbacks.set_active(&dispatch, number, event.active, timestamp);
+                callbacks.matching(|kind| kind.matches(event.active))
+            };
+            for callback in matching {
+                (callback.lock().unwrap())(event);
+            }
+        }),
+    )?;
+    // Changes may have been missed while the interrupt wasn't installed
+    let active = (pin.read() == Level::High) == active_state;
+    drop(pin);
+    let mut registry = callbacks.lock().unwrap();
+    registry.installed = true;
+    if active != registry.active_since.is_some() {
+        registry.set_active(callbacks, number, active, Instant::now());
+    }
+    Ok(())
+}
+
+/// Registers `callback` for the changes matching `kind`, installing the async interrupt
+/// dispatching to the callbacks of the device if needed.
+pub(crate) fn subscribe(
+    pin: &Mutex<Box<dyn InputPin>>,
+    callbacks: &SharedCallbacks,
+    active_state: bool,
+    kind: EventKind,
+    callback: Box<dyn FnMut(Event) + Send>,
+) -> Result<CallbackHandle> {
+    let installed = callbacks.lock().unwrap().installed;
+    if !installed {
+        install(pin, callbacks, active_state)?;
+    }
+    let mut registry = callbacks.lock().unwrap();
+    let id = registry.next_id;
+    registry.next_id += 1;
+    registry
+        .entries
+        .push((id, kind, Arc::new(Mutex::new(callback))));
+    Ok(CallbackHandle {
+        id,
+        callbacks: Arc::downgrade(callbacks),
+    })
+}
+
+/// Identifies a callback registered on an input device (e.g. with `when_activated`), so that
+/// it can be removed without affecting the other callbacks of the device.
+/// Dropping the handle leaves the callback registered.
+#[derive(Debug)]
+pub struct CallbackHandle {
+    id: u64,
+    callbacks: Weak<Mutex<Callbacks>>,
+}
+
+impl CallbackHandle {
+    /// Removes the callback. Does nothing if the device was dropped, or its callbacks cleared.
+    pub fn remove(self) {
+        if let Some(callbacks) = self.callbacks.upgrade() {
+            let removed = {
+                let mut callbacks = callbacks.lock().unwrap();
+                let index = callbacks
+                    .entries
+                    .iter()
+                    .position(|(id, _, _)| *id == self.id);
+                index.map(|index| callbacks.entries.remove(index))
+            };
+            // Dropped outside the lock, in case the callback captured something re-entrant
+            drop(removed);
+        }
+    }
+}
+
+/// Longest time a blocking wait holds the lock on a shared input pin
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Blocks until the interrupt configured on the pin fires, or the timeout is reached.
+/// The pin is only locked for short polls, so that it can still be read by other threads
+/// while waiting.
+pub(crate) fn poll_interrupt(
+    pin: &Mutex<Box<dyn InputPin>>,
+    reset: bool,
+    timeout: Option<Duration>,
+) -> Result<Option<Level>> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut reset = reset;
+    loop {
+        let interval = match deadline {
+            Some(deadline) => deadline
+                .saturating_duration_since(Instant::now())
+                .min(POLL_INTERVAL),
+            None => POLL_INTERVAL,
+        };
+        if let Some(level) = pin.lock().unwrap().poll_interrupt(reset, Some(interval))? {
+            return Ok(Some(level));
+        }
+        reset = false;
+        if let Some(deadline) = deadline {
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+macro_rules! impl_input_device {
+    () => {
+        /// The `Pin` that the device is connected to.
+        pub fn pin(&self) -> u8 {
+            self.pin.lock().unwrap().pin()
+        }
+
+        /// Returns ``True`` if the device is currently active and ``False`` otherwise.
+        pub fn value(&self) -> bool {
+            (self.pin.lock().unwrap().read() == Level::High) == self.active_state
+        }
+
+        /// Returns an endless iterator over the device's value, read each time the iterator
+        /// is advanced. Pass it to `set_source` on an output device to feed this
+        /// device's values into it.
+        pub fn values(&self) -> impl Iterator<Item = bool> + Send + 'static {
+            let pin = Arc::clone(&self.pin);
+            let active_state = self.active_state;
+            std::iter::repeat_with(move || {
+                (pin.lock().unwrap().read() == Level::High) == active_state
+            })
+        }
+    };
+}
+
+macro_rules! impl_events_mixin {
+    () => {
+        /// Pause the program until the device is activated, or the timeout is reached.
+        #[allow(dead_code)]
+        fn wait_for(&mut self, timeout: Option<f32>, active: bool) {
+            let trigger = match active {
+                true => Trigger::RisingEdge,
+                false => Trigger::FallingEdge,
+            };
+            let timeout = timeout.map(|seconds| Duration::from_millis((seconds * 1000.0) as u64));
+            self.pin.lock().unwrap().set_interrupt(trigger).unwrap();
+            poll_interrupt(&self.pin, true, timeout).unwrap();
+            self.restore_callbacks().unwrap();
+        }
+
+        /// Reinstalls the async interrupt of the callbacks, which pins drop when waiting for
+        /// a synchronous interrupt.
+        pub(crate) fn restore_callbacks(&self) -> Result<()> {
+            let installed = self.callbacks.lock().unwrap().installed;
+            if installed {
+                install(&self.pin, &self.callbacks, self.active_state)?;
+            }
+            Ok(())
+        }
+
+        fn subscribe<C>(&mut self, kind: EventKind, callback: C) -> Result<CallbackHandle>
+        where
+            C: FnMut(Event) + Send + 'static,
+        {
+            subscribe(
+                &self.pin,
+                &self.callbacks,
+                self.active_state,
+                kind,
+                Box::new(callback),
+            )
+        }
+
+        /// Invokes the passed closure every time the device is activated.
+        /// Callbacks run on a background thread, in the order they were registered, and
+        /// several can be registered for the same event.
+        /// Returns a handle to remove this callback without affecting the others.
+        pub fn when_activated<C>(&mut self, callback: C) -> Result<CallbackHandle>
+        where
+            C: FnMut(Event) + Send + 'static,
+        {
+            self.subscribe(EventKind::Activated, callback)
+        }
+
+        /// Invokes the passed closure every time the device is deactivated.
+        /// Returns a handle to remove this callback without affecting the others.
+        pub fn when_deactivated<C>(&mut self, callback: C) -> Result<CallbackHandle>
+        where
+            C: FnMut(Event) + Send + 'static,
+        {
+            self.subscribe(EventKind::Deactivated, callback)
+        }
+
+        /// Invokes the passed closure every time the device is activated or deactivated.
+        /// Returns a handle to remove this callback without affecting the others.
+        pub fn when_changed<C>(&mut self, callback: C) -> Result<CallbackHandle>
+        where
+            C: FnMut(Event) + Send + 'static,
+        {
+            self.subscribe(EventKind::Changed, callback)
+        }
+
+        /// Removes all the callbacks of the device, and the async interrupt invoking them
+        pub fn clear_async_interrupt(&mut self) -> Result<()> {
+            let (entries, timer) = {
+                let mut callbacks = self.callbacks.lock().unwrap();
+                callbacks.installed = false;
+                callbacks.active_since = None;
+                callbacks.pressed_since = None;
+                (
+                    std::mem::take(&mut callbacks.entries),
+                    callbacks.hold_timer.take(),
+                )
+            };
+            if let Some(timer) = timer {
+                timer.abort();
+            }
+            drop(entries);
+            self.pin.lock().unwrap().clear_async_interrupt()
+        }
+    };
+}
 
 /// Represents a generic GPIO input device.
 #[derive(Debug)]
 pub struct InputDevice {
-    pin: InputPin,
+    pin: SharedInputPin,
     active_state: bool,
-    inactive_state: bool,
+    callbacks: SharedCallbacks,
 }
 
 impl InputDevice {
@@ -19,18 +532,22 @@ impl InputDevice {
     /// * `pin` - The GPIO pin which the device is attached to
     ///
     pub fn new(pin: u8) -> InputDevice {
-        match Gpio::new() {
+        match InputDevice::try_new(pin) {
             Err(e) => panic!("{:?}", e),
-            Ok(gpio) => match gpio.get(pin) {
-                Err(e) => panic!("{:?}", e),
-                Ok(pin) => InputDevice {
-                    pin: pin.into_input_pulldown(),
-                    active_state: true,
-                    inactive_state: false,
-                },
-            },
+            Ok(device) => device,
         }
     }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<InputDevice> {
+        Ok(InputDevice {
+            pin: Arc::new(Mutex::new(
+                default_factory().input(pin, PullUpDown::PullDown)?,
+            )),
+            active_state: true,
+            callbacks: SharedCallbacks::default(),
+        })
+    }
     /// Returns an InputDevice with the pin number given with the pin pulled high with an internal resistor by default
     /// `is_active` property is adjusted accordingly so that
     /// ``True`` still means active regardless of the :attr:`pull_up` setting
@@ -39,48 +556,76 @@ impl InputDevice {
     /// * `pin` - The GPIO pin which the device is attached to
     ///
     pub fn new_with_pullup(pin: u8) -> InputDevice {
-        match Gpio::new() {
+        match InputDevice::try_new_with_pullup(pin) {
             Err(e) => panic!("{:?}", e),
-            Ok(gpio) => match gpio.get(pin) {
-                Err(e) => panic!("{:?}", e),
-                Ok(pin) => InputDevice {
-                    pin: pin.into_input_pullup(),
-                    active_state: false,
-                    inactive_state: true,
-                },
-            },
+            Ok(device) => device,
         }
     }
 
-    impl_device!();
-    impl_gpio_device!();
-    impl_io_device!();
-}
+    /// Like `new_with_pullup`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new_with_pullup(pin: u8) -> Result<InputDevice> {
+        Ok(InputDevice {
+            pin: Arc::new(Mutex::new(
+                default_factory().input(pin, PullUpDown::PullUp)?,
+            )),
+            active_state: false,
+            callbacks: SharedCallbacks::default(),
+        })
+    }
 
-macro_rules! impl_events_mixin {
-    () => {
-        /// Pause the program until the device is activated, or the timeout is reached.
-        fn wait_for(&mut self, timeout: Option<f32>, active: bool) {
-            let trigger = match active {
-                true => Trigger::RisingEdge,
-                false => Trigger::FallingEdge,
-            };
-            let timeout = timeout.map(|seconds| Duration::from_millis((seconds * 1000.0) as u64));
-            self.pin.set_interrupt(trigger).unwrap();
-            self.pin.poll_interrupt(true, timeout).unwrap();
-        }
-    };
+    impl_device!();
+    impl_input_device!();
+    impl_events_mixin!();
 }
 
 /// Represents a generic input device with typical on/off behaviour.
 /// Adds machinery to fire the active and inactive events for devices
 /// that operate in a typical digital manner: straight forward on / off
 /// states with (reasonably) clean transitions between the two.
+///
+/// # Example
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut sensor = DigitalInputDevice::new(17);
+/// let activations = Arc::new(AtomicUsize::new(0));
+/// let changes = Arc::new(AtomicUsize::new(0));
+/// let count = activations.clone();
+/// let first = sensor
+///     .when_activated(move |_| {
+///         count.fetch_add(1, Ordering::SeqCst);
+///     })
+///     .unwrap();
+/// let count = changes.clone();
+/// sensor
+///     .when_changed(move |_| {
+///         count.fetch_add(1, Ordering::SeqCst);
+///     })
+///     .unwrap();
+///
+/// factory.get(17).drive_high();
+/// factory.get(17).drive_low();
+/// assert_eq!(activations.load(Ordering::SeqCst), 1);
+/// assert_eq!(changes.load(Ordering::SeqCst), 2);
+///
+/// // Removing one callback leaves the other registered
+/// first.remove();
+/// factory.get(17).drive_high();
+/// assert_eq!(activations.load(Ordering::SeqCst), 1);
+/// assert_eq!(changes.load(Ordering::SeqCst), 3);
+/// ```
 #[derive(Debug)]
 pub struct DigitalInputDevice {
-    pin: InputPin,
+    pin: SharedInputPin,
     active_state: bool,
-    inactive_state: bool,
+    callbacks: SharedCallbacks,
 }
 
 impl DigitalInputDevice {
@@ -94,18 +639,22 @@ impl DigitalInputDevice {
     /// These pins are not suitable for use where no pullup resistor is required
     /// Source: https://pinout.xyz/pinout/pin5_gpio3
     pub fn new(pin: u8) -> DigitalInputDevice {
-        match Gpio::new() {
+        match DigitalInputDevice::try_new(pin) {
             Err(e) => panic!("{:?}", e),
-            Ok(gpio) => match gpio.get(pin) {
-                Err(e) => panic!("{:?}", e),
-                Ok(pin) => DigitalInputDevice {
-                    pin: pin.into_input_pulldown(),
-                    active_state: true,
-                    inactive_state: false,
-                },
-            },
+            Ok(device) => device,
         }
     }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<DigitalInputDevice> {
+        Ok(DigitalInputDevice {
+            pin: Arc::new(Mutex::new(
+                default_factory().input(pin, PullUpDown::PullDown)?,
+            )),
+            active_state: true,
+            callbacks: SharedCallbacks::default(),
+        })
+    }
     /// Returns a DigitalInputDevice with the pin number given with the pin pulled high with an internal resistor by default
     /// `is_active` property is adjusted accordingly so that
     /// ``True`` still means active regardless of the :attr:`pull_up` setting
@@ -114,22 +663,25 @@ impl DigitalInputDevice {
     /// * `pin` - The GPIO pin which the device is attached to
     ///
     pub fn new_with_pullup(pin: u8) -> DigitalInputDevice {
-        match Gpio::new() {
+        match DigitalInputDevice::try_new_with_pullup(pin) {
             Err(e) => panic!("{:?}", e),
-            Ok(gpio) => match gpio.get(pin) {
-                Err(e) => panic!("{:?}", e),
-                Ok(pin) => DigitalInputDevice {
-                    pin: pin.into_input_pullup(),
-                    active_state: false,
-                    inactive_state: true,
-                },
-            },
+            Ok(device) => device,
         }
     }
 
+    /// Like `new_with_pullup`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new_with_pullup(pin: u8) -> Result<DigitalInputDevice> {
+        Ok(DigitalInputDevice {
+            pin: Arc::new(Mutex::new(
+                default_factory().input(pin, PullUpDown::PullUp)?,
+            )),
+            active_state: false,
+            callbacks: SharedCallbacks::default(),
+        })
+    }
+
     impl_device!();
-    impl_gpio_device!();
-    impl_io_device!();
+    impl_input_device!();
     impl_events_mixin!();
 
     /// Pause the program until the device is deactivated, or the timeout is reached.
@@ -147,47 +699,87 @@ impl DigitalInputDevice {
 /// Connect one side of the button to a ground pin, and the other to any GPIO pin. The GPIO pin will be pulled high by default.
 /// Alternatively, connect one side of the button to the 3V3 pin, and the other to any GPIO pin,
 /// and then create a Button instance with Button::new_with_pulldown
+///
+/// # Example
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+/// use std::sync::mpsc;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut button = Button::new(17);
+/// let (sender, events) = mpsc::channel();
+/// button
+///     .when_changed(move |event| sender.send(event).unwrap())
+///     .unwrap();
+///
+/// // The pin of a pull-up button goes low when it is pressed
+/// factory.get(17).drive_low();
+/// factory.get(17).drive_high();
+/// let press = events.recv().unwrap();
+/// let release = events.recv().unwrap();
+/// assert!(press.active && !release.active);
+/// assert_eq!(press.pin, 17);
+/// assert_eq!(press.since_previous, None);
+/// assert_eq!(
+///     release.since_previous,
+///     Some(release.timestamp - press.timestamp)
+/// );
+/// ```
 pub struct Button {
-    pub(crate) pin: InputPin,
+    pub(crate) pin: SharedInputPin,
     active_state: bool,
-    inactive_state: bool,
+    callbacks: SharedCallbacks,
+    click_timeout: Duration,
 }
 
 impl Button {
     /// Returns a Button with the pin number given and the pin pulled high with an internal resistor by default
     /// * `pin` - The GPIO pin which the device is attached to
     pub fn new(pin: u8) -> Button {
-        match Gpio::new() {
+        match Button::try_new(pin) {
             Err(e) => panic!("{:?}", e),
-            Ok(gpio) => match gpio.get(pin) {
-                Err(e) => panic!("{:?}", e),
-                Ok(pin) => Button {
-                    pin: pin.into_input_pullup(),
-                    active_state: false,
-                    inactive_state: true,
-                },
-            },
+            Ok(device) => device,
         }
     }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<Button> {
+        Ok(Button {
+            pin: Arc::new(Mutex::new(
+                default_factory().input(pin, PullUpDown::PullUp)?,
+            )),
+            active_state: false,
+            callbacks: SharedCallbacks::default(),
+            click_timeout: DEFAULT_CLICK_TIMEOUT,
+        })
+    }
     /// Returns a Button with the pin number given and the pin pulled down with an internal resistor by default
     /// * `pin` - The GPIO pin which the device is attached to
     pub fn new_with_pulldown(pin: u8) -> Button {
-        match Gpio::new() {
+        match Button::try_new_with_pulldown(pin) {
             Err(e) => panic!("{:?}", e),
-            Ok(gpio) => match gpio.get(pin) {
-                Err(e) => panic!("{:?}", e),
-                Ok(pin) => Button {
-                    pin: pin.into_input_pulldown(),
-                    active_state: true,
-                    inactive_state: false,
-                },
-            },
+            Ok(device) => device,
         }
     }
 
+    /// Like `new_with_pulldown`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new_with_pulldown(pin: u8) -> Result<Button> {
+        Ok(Button {
+            pin: Arc::new(Mutex::new(
+                default_factory().input(pin, PullUpDown::PullDown)?,
+            )),
+            active_state: true,
+            callbacks: SharedCallbacks::default(),
+            click_timeout: DEFAULT_CLICK_TIMEOUT,
+        })
+    }
+
     impl_device!();
-    impl_gpio_device!();
-    impl_io_device!();
+    impl_input_device!();
     impl_events_mixin!();
 
     //// Pause the program until the device is deactivated, or the timeout is reached.
@@ -202,36 +794,289 @@ impl Button {
         self.wait_for(timeout, true)
     }
 
-    /// Invokes the passed closure everytime the button is pressed
-    pub fn when_pressed<C>(&mut self, action: C) -> Result<(), gpio::Error>
+    /// Invokes the passed closure everytime the button is pressed.
+    /// Returns a handle to remove this callback without affecting the others.
+    pub fn when_pressed<C>(&mut self, action: C) -> Result<CallbackHandle>
     where
-        C: FnMut(Level) + Send + 'static,
+        C: FnMut(Event) + Send + 'static,
     {
         self.action_on(true, action)
     }
 
-    /// Invokes the passed closure everytime the button is released
-    pub fn when_released<C>(&mut self, action: C) -> Result<(), gpio::Error>
+    /// Invokes the passed closure everytime the button is released.
+    /// Returns a handle to remove this callback without affecting the others.
+    pub fn when_released<C>(&mut self, action: C) -> Result<CallbackHandle>
     where
-        C: FnMut(Level) + Send + 'static,
+        C: FnMut(Event) + Send + 'static,
     {
         self.action_on(false, action)
     }
 
-    /// Adds an async interrupt for the corresponding trigger type to support `when_pressed`/`when_released`
-    pub(crate) fn action_on<C>(&mut self, active: bool, action: C) -> Result<(), gpio::Error>
+    /// Invokes the passed closure once the button has been held down for `hold_time`, and
+    /// then every `hold_time` while it is still held if `hold_repeat` is set.
+    /// A callback registered while the button is pressed first fires on the next press.
+    /// Callbacks run on the shared scheduler thread, so they should return quickly.
+    /// Returns a handle to remove this callback without affecting the others.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_gpiozero::pins::{self, MockFactory};
+    /// use rust_gpiozero::*;
+    /// use std::sync::mpsc;
+    /// use std::time::Duration;
+    ///
+    /// let factory = MockFactory::new();
+    /// pins::set_default_factory(factory.clone());
+    ///
+    /// let mut button = Button::new(17);
+    /// button.set_hold_time(0.05).unwrap();
+    /// let (sender, holds) = mpsc::channel();
+    /// button.when_held(move |event| sender.send(event).unwrap()).unwrap();
+    ///
+    /// // A short press isn't a hold
+    /// factory.get(17).drive_low();
+    /// factory.get(17).drive_high();
+    /// assert!(holds.recv_timeout(Duration::from_millis(100)).is_err());
+    ///
+    /// factory.get(17).drive_low();
+    /// let hold = holds.recv().unwrap();
+    /// assert!(hold.active && button.is_held());
+    /// assert!(hold.since_previous.unwrap() >= Duration::from_millis(50));
+    /// factory.get(17).drive_high();
+    /// assert!(!button.is_held());
+    /// ```
+    pub fn when_held<C>(&mut self, action: C) -> Result<CallbackHandle>
     where
-        C: FnMut(Level) + Send + 'static,
+        C: FnMut(Event) + Send + 'static,
     {
-        let trigger = match active {
-            true => Trigger::RisingEdge,
-            false => Trigger::FallingEdge,
-        };
-        self.pin.set_async_interrupt(trigger, action)
+        self.subscribe(EventKind::Held, action)
+    }
+
+    /// The number of seconds the button must be held down for before it is considered held.
+    /// Defaults to 1 second.
+    pub fn hold_time(&self) -> f32 {
+        self.callbacks.lock().unwrap().hold_time.as_secs_f32()
+    }
+
+    /// Sets the number of seconds the button must be held down for before it is considered
+    /// held, which is also the interval of repeated hold events. The `when_held` callbacks
+    /// of a press in progress still fire after the previous hold time.
+    ///
+    /// Also starts tracking the presses of the button for `is_held` and `held_time`, by
+    /// installing its async interrupt if it isn't already.
+    /// Returns `Error::OutOfRange` if the time isn't positive, or an error if the interrupt
+    /// can't be installed.
+    pub fn set_hold_time(&mut self, seconds: f32) -> Result<()> {
+        if !(seconds > 0.0 && seconds.is_finite()) {
+            return Err(Error::OutOfRange {
+                value: f64::from(seconds),
+                min: 0.0,
+                max: f64::INFINITY,
+            });
+        }
+        self.track_presses()?;
+        self.callbacks.lock().unwrap().hold_time = Duration::from_secs_f32(seconds);
+        Ok(())
+    }
+
+    /// Returns `true` if the `when_held` callbacks are invoked repeatedly while the button is
+    /// held, rather than once per press.
+    pub fn hold_repeat(&self) -> bool {
+        self.callbacks.lock().unwrap().hold_repeat
+    }
+
+    /// When `true`, the `when_held` callbacks are invoked every `hold_time` for as long as
+    /// the button is held. Defaults to `false`.
+    pub fn set_hold_repeat(&mut self, repeat: bool) {
+        self.callbacks.lock().unwrap().hold_repeat = repeat;
+    }
+
+    /// Returns `true` if the button has been pressed for at least `hold_time`.
+    ///
+    /// Presses are tracked by an async interrupt, installed by `set_hold_time`,
+    /// `set_press_stats` or a method registering a callback such as `when_held`, so this
+    /// returns `false` until one of them is called. A press in progress when the interrupt
+    /// is installed counts from that point on, and presses aren't tracked after
+    /// `clear_async_interrupt`, until the interrupt is installed again.
+    pub fn is_held(&self) -> bool {
+        self.held_time().is_some()
+    }
+
+    /// The number of seconds the button has been pressed for, once it is held, or `None` if
+    /// it isn't held. Presses are tracked like for `is_held`.
+    pub fn held_time(&self) -> Option<f32> {
+        let callbacks = self.callbacks.lock().unwrap();
+        let pressed = callbacks.active_since?.elapsed();
+        if pressed >= callbacks.hold_time {
+            Some(pressed.as_secs_f32())
+        } else {
+            None
+        }
+    }
+
+    /// Invokes the passed closure with the gestures made with the button: `Click(n)` once the
+    /// button was clicked `n` times in a row, `LongPress` once it has been held down for
+    /// `hold_time`, and `Release` when it is released after a long press.
+    /// A click must follow the previous one within `click_timeout` to count towards the same
+    /// gesture, so a single click is only reported once that time is over.
+    /// The `hold_time` and `click_timeout` of the button when the callback is registered apply.
+    /// Returns a handle to remove this callback without affecting the others.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_gpiozero::pins::{self, MockFactory};
+    /// use rust_gpiozero::*;
+    /// use std::sync::mpsc;
+    ///
+    /// let factory = MockFactory::new();
+    /// pins::set_default_factory(factory.clone());
+    ///
+    /// let mut button = Button::new(17);
+    /// button.set_click_timeout(0.1).unwrap();
+    /// button.set_hold_time(0.2).unwrap();
+    /// let (sender, gestures) = mpsc::channel();
+    /// button
+    ///     .when_gesture(move |gesture| sender.send(gesture).unwrap())
+    ///     .unwrap();
+    ///
+    /// let pin = factory.get(17);
+    /// for _ in 0..2 {
+    ///     pin.drive_low();
+    ///     pin.drive_high();
+    /// }
+    /// assert_eq!(gestures.recv().unwrap(), Gesture::Click(2));
+    ///
+    /// pin.drive_low();
+    /// assert_eq!(gestures.recv().unwrap(), Gesture::LongPress);
+    /// pin.drive_high();
+    /// assert_eq!(gestures.recv().unwrap(), Gesture::Release);
+    /// ```
+    pub fn when_gesture<C>(&mut self, callback: C) -> Result<CallbackHandle>
+    where
+        C: FnMut(Gesture) + Send + 'static,
+    {
+        let recognizer = self.gesture_recognizer(callback);
+        self.subscribe(EventKind::Changed, recognizer)
+    }
+
+    /// The number of seconds within which a click must follow the previous one to make a
+    /// double or triple click. Defaults to 0.3 seconds.
+    pub fn click_timeout(&self) -> f32 {
+        self.click_timeout.as_secs_f32()
     }
 
-    /// Removes all previously configured async trigger(s) (E.g. `when_pressed`/`when_released`)
-    pub fn clear_async_interrupt(&mut self) -> Result<(), gpio::Error> {
-        self.pin.clear_async_interrupt()
+    /// Sets the number of seconds within which a click must follow the previous one to make
+    /// a double or triple click.
+    /// Returns `Error::OutOfRange` if the time isn't positive.
+    pub fn set_click_timeout(&mut self, seconds: f32) -> Result<()> {
+        if !(seconds > 0.0 && seconds.is_finite()) {
+            return Err(Error::OutOfRange {
+                value: f64::from(seconds),
+                min: 0.0,
+                max: f64::INFINITY,
+            });
+        }
+        self.click_timeout = Duration::from_secs_f32(seconds);
+        Ok(())
+    }
+
+    /// Enables or disables counting and timing the presses of the button, which is disabled
+    /// by default. Disabling it discards the statistics gathered so far.
+    ///
+    /// Enabling the statistics installs the async interrupt tracking the presses of the
+    /// button, if it isn't already. Presses are only counted while it is installed, so not
+    /// after `clear_async_interrupt` until a callback is registered or this method is called
+    /// again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_gpiozero::pins::{self, MockFactory};
+    /// use rust_gpiozero::*;
+    ///
+    /// let factory = MockFactory::new();
+    /// pins::set_default_factory(factory.clone());
+    ///
+    /// let mut button = Button::new(17);
+    /// assert_eq!(button.press_stats(), None);
+    /// button.set_press_stats(true).unwrap();
+    ///
+    /// for _ in 0..3 {
+    ///     factory.get(17).drive_low();
+    ///     factory.get(17).drive_high();
+    /// }
+    /// let stats = button.press_stats().unwrap();
+    /// assert_eq!(stats.count(), 3);
+    /// assert!(stats.mean_duration() <= stats.max_duration());
+    /// assert_eq!(
+    ///     stats.last_duration(),
+    ///     Some(stats.last_released().unwrap() - stats.last_pressed().unwrap())
+    /// );
+    ///
+    /// button.reset_press_stats();
+    /// assert_eq!(button.press_stats().unwrap().count(), 0);
+    /// ```
+    pub fn set_press_stats(&mut self, enabled: bool) -> Result<()> {
+        if enabled {
+            self.track_presses()?;
+        }
+        let mut callbacks = self.callbacks.lock().unwrap();
+        match enabled {
+            true => {
+                callbacks.stats.get_or_insert_with(PressStats::default);
+            }
+            false => callbacks.stats = None,
+        }
+        Ok(())
+    }
+
+    /// Returns the statistics of the presses of the button since they were enabled or last
+    /// reset, or `None` if they aren't enabled.
+    pub fn press_stats(&self) -> Option<PressStats> {
+        self.callbacks.lock().unwrap().stats
+    }
+
+    /// Resets the statistics of the presses of the button, if they are enabled.
+    pub fn reset_press_stats(&mut self) {
+        if let Some(stats) = &mut self.callbacks.lock().unwrap().stats {
+            *stats = PressStats::default();
+        }
+    }
+
+    /// Only counts the changes accepted by `filter` in the statistics of the presses
+    pub(crate) fn filter_presses(&self, filter: PressFilter) {
+        self.callbacks.lock().unwrap().press_filter = Some(filter);
+    }
+
+    /// Installs the async interrupt tracking the presses of the button, if it isn't already
+    fn track_presses(&self) -> Result<()> {
+        let installed = self.callbacks.lock().unwrap().installed;
+        if !installed {
+            install(&self.pin, &self.callbacks, self.active_state)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a callback for the changes of the button, recognizing the gestures they make
+    pub(crate) fn gesture_recognizer<C>(&self, callback: C) -> impl FnMut(Event) + Send + 'static
+    where
+        C: FnMut(Gesture) + Send + 'static,
+    {
+        let hold_time = self.callbacks.lock().unwrap().hold_time;
+        Recognizer::callback(self.click_timeout, hold_time, Box::new(callback))
+    }
+
+    /// Registers a callback for presses (`active`) or releases, to support `when_pressed`/`when_released`
+    pub(crate) fn action_on<C>(&mut self, active: bool, action: C) -> Result<CallbackHandle>
+    where
+        C: FnMut(Event) + Send + 'static,
+    {
+        let kind = match active {
+            true => EventKind::Activated,
+            false => EventKind::Deactivated,
+        };
+        self.subscribe(kind, action)
     }
 }
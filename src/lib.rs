@@ -16,7 +16,7 @@
 //!
 //! # Example : Blinking an LED
 //!
-//! ```
+//! ```no_run
 //! use rust_gpiozero::*;
 //!
 //! // Create a new LED attached to Pin 17
@@ -27,10 +27,14 @@
 //! led.blink(2.0,3.0);
 //! ```
 
+pub use self::boards::*;
 pub use self::devices::*;
+pub use self::error::{Error, Result};
 pub use self::input_devices::*;
 pub use self::output_devices::*;
 
+#[macro_use]
+mod source;
 #[macro_use]
 pub mod devices;
 #[macro_use]
@@ -38,5 +42,19 @@ pub mod output_devices;
 #[macro_use]
 pub mod input_devices;
 
+pub mod boards;
+pub mod error;
+pub mod pins;
+pub mod tones;
+pub mod tools;
+
 mod debounce;
 pub use debounce::{Debounce, Debounced};
+
+mod gestures;
+pub use gestures::Gesture;
+
+mod fade;
+pub use fade::Easing;
+mod scheduler;
+pub use scheduler::BlinkHandle;
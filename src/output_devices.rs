@@ -1,19 +1,26 @@
 //! Output device component interfaces for devices such as `LED`, `PWMLED`, etc
-use rppal::gpio::{Gpio, IoPin, Level, Mode};
-use std::sync::atomic::{AtomicBool, Ordering};
+use crate::error::{Error, Result};
+use crate::fade::{blink_cycle, Easing, Fader};
+use crate::pins::{default_factory, Level, OutputPin};
+use crate::scheduler::{self, BlinkHandle, Task, TaskHandle};
+use crate::source::{SourcePump, DEFAULT_SOURCE_DELAY};
+use crate::tones::Tone;
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::thread;
-use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// Represents a generic GPIO output device.
 #[derive(Debug)]
 pub struct OutputDevice {
-    pin: IoPin,
+    pin: Box<dyn OutputPin>,
     active_state: bool,
     inactive_state: bool,
+    pwm_value: f64,
+    pwm_frequency: f64,
 }
+
+/// Frequency in Hz of the PWM signal of devices, unless changed with `set_frequency`
+const DEFAULT_PWM_FREQUENCY: f64 = 100.0;
 #[macro_export]
 macro_rules! impl_io_device {
     () => {
@@ -90,21 +97,28 @@ macro_rules! impl_output_device {
 
 impl OutputDevice {
     /// Returns an OutputDevice with the pin number given
-
     ///
     /// * `pin` - The GPIO pin which the device is attached to
     ///  
     pub fn new(pin: u8) -> OutputDevice {
-        match Gpio::new() {
+        match OutputDevice::try_new(pin) {
             Err(e) => panic!("{:?}", e),
-            Ok(gpio) => match gpio.get(pin) {
-                Err(e) => panic!("{:?}", e),
-                Ok(pin) => OutputDevice {
-                    pin: pin.into_io(Mode::Output),
-                    active_state: true,
-                    inactive_state: false,
-                },
-            },
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<OutputDevice> {
+        Ok(OutputDevice::with_pin(default_factory().output(pin)?))
+    }
+
+    fn with_pin(pin: Box<dyn OutputPin>) -> OutputDevice {
+        OutputDevice {
+            pin,
+            active_state: true,
+            inactive_state: false,
+            pwm_value: 0.0,
+            pwm_frequency: DEFAULT_PWM_FREQUENCY,
         }
     }
 
@@ -115,51 +129,38 @@ impl OutputDevice {
 }
 
 /// Represents a generic output device with typical on/off behaviour.
-/// Extends behaviour with a blink() method which uses a background
-/// thread to handle toggling the device state without further interaction.
+/// Extends behaviour with a blink() method which toggles the device state in the background
+/// without further interaction. The blink, beep and pulse patterns of all devices share a
+/// single scheduler thread, and endless patterns with the same timing stay in lock-step.
 #[derive(Debug)]
 pub struct DigitalOutputDevice {
     device: Arc<Mutex<OutputDevice>>,
-    blinking: Arc<AtomicBool>,
-    handle: Option<JoinHandle<()>>,
+    handle: Option<TaskHandle>,
     blink_count: Option<i32>,
+    source: Mutex<Option<SourcePump>>,
+    source_delay: f32,
 }
 
 macro_rules! impl_digital_output_device {
     () => {
-        fn blinker(&mut self, on_time: f32, off_time: f32, n: Option<i32>) {
+        fn blinker(&mut self, on_time: f32, off_time: f32, n: Option<i32>) -> BlinkHandle {
             self.stop();
 
             let device = Arc::clone(&self.device);
-            let blinking = Arc::clone(&self.blinking);
-
-            self.handle = Some(thread::spawn(move || {
-                blinking.store(true, Ordering::SeqCst);
-                match n {
-                    Some(end) => {
-                        for _ in 0..end {
-                            if !blinking.load(Ordering::SeqCst) {
-                                device.lock().unwrap().off();
-                                break;
-                            }
-                            device.lock().unwrap().on();
-                            thread::sleep(Duration::from_millis((on_time * 1000.0) as u64));
-                            device.lock().unwrap().off();
-                            thread::sleep(Duration::from_millis((off_time * 1000.0) as u64));
-                        }
-                    }
-                    None => loop {
-                        if !blinking.load(Ordering::SeqCst) {
-                            device.lock().unwrap().off();
-                            break;
-                        }
-                        device.lock().unwrap().on();
-                        thread::sleep(Duration::from_millis((on_time * 1000.0) as u64));
-                        device.lock().unwrap().off();
-                        thread::sleep(Duration::from_millis((off_time * 1000.0) as u64));
-                    },
+            let cycle = blink_cycle(on_time, off_time, 0.0, 0.0);
+            let blink = Fader::default().blink(cycle, n, move |level| {
+                let mut device = device.lock().unwrap();
+                if level == 1.0 {
+                    device.on()
+                } else {
+                    device.off()
                 }
-            }));
+                Ok(())
+            });
+            let handle = scheduler::schedule(blink);
+            let completion = handle.completion();
+            self.handle = Some(handle);
+            completion
         }
         /// Returns ``True`` if the device is currently active and ``False`` otherwise.
         pub fn is_active(&self) -> bool {
@@ -177,8 +178,13 @@ macro_rules! impl_digital_output_device {
         }
 
         /// Reverse the state of the device. If it's on, turn it off; if it's off, turn it on.
+        /// Stops any blink pattern or source.
         pub fn toggle(&mut self) {
-            self.device.lock().unwrap().toggle()
+            if self.value() {
+                self.off()
+            } else {
+                self.on()
+            }
         }
 
         /// Returns ``True`` if the device is currently active and ``False`` otherwise.
@@ -186,9 +192,13 @@ macro_rules! impl_digital_output_device {
             self.device.lock().unwrap().value()
         }
 
+        /// Cancels any blink pattern and source, and turns the device off.
         fn stop(&self) {
-            self.blinking.clone().store(false, Ordering::SeqCst);
-            self.device.lock().unwrap().pin.set_low();
+            self.clear_source();
+            if let Some(handle) = &self.handle {
+                handle.cancel();
+            }
+            self.device.lock().unwrap().off();
         }
 
         /// When ``True``, the `value` property is ``True`` when the device's
@@ -215,25 +225,51 @@ macro_rules! impl_digital_output_device {
             drop(self)
         }
 
-        /// Block until background process is done
+        /// Block until background process is done. Returns immediately if the device isn't
+        /// blinking, and never returns for endless patterns.
         pub fn wait(&mut self) {
-            self.handle
-                .take()
-                .expect("Called stop on non-running thread")
-                .join()
-                .expect("Could not join spawned thread");
+            if let Some(handle) = &self.handle {
+                handle.completion().wait();
+            }
+        }
+
+        /// Stops blinking and returns the function writing source values to the device.
+        /// Any non-zero value turns the device on.
+        fn source_writer(&mut self) -> impl FnMut(f64) -> Result<()> + Send + 'static {
+            self.stop();
+            let device = Arc::clone(&self.device);
+            move |value| {
+                let mut device = device.lock().unwrap();
+                if value != 0.0 {
+                    device.on()
+                } else {
+                    device.off()
+                }
+                Ok(())
+            }
         }
+
+        impl_source!();
     };
 }
 
 impl DigitalOutputDevice {
     pub fn new(pin: u8) -> DigitalOutputDevice {
-        DigitalOutputDevice {
-            device: Arc::new(Mutex::new(OutputDevice::new(pin))),
-            blinking: Arc::new(AtomicBool::new(false)),
+        match DigitalOutputDevice::try_new(pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<DigitalOutputDevice> {
+        Ok(DigitalOutputDevice {
+            device: Arc::new(Mutex::new(OutputDevice::try_new(pin)?)),
             handle: None,
             blink_count: None,
-        }
+            source: Mutex::new(None),
+            source_delay: DEFAULT_SOURCE_DELAY,
+        })
     }
 
     impl_digital_output_device!();
@@ -244,10 +280,30 @@ impl DigitalOutputDevice {
     /// * `off_time` - Number of seconds off
     ///
     pub fn blink(&mut self, on_time: f32, off_time: f32) {
-        match self.blink_count {
-            None => self.blinker(on_time, off_time, None),
-            Some(n) => self.blinker(on_time, off_time, Some(n)),
+        self.blinker(on_time, off_time, self.blink_count);
+    }
+
+    /// Make the device turn on and off `n` times, or endlessly if `n` is `None`, ignoring
+    /// the count set with `set_blink_count`. The device is off once all blinks are done.
+    /// If `background` is `false`, blocks until the blinks are done,
+    /// unless called from a callback on the scheduler thread (see `BlinkHandle::wait`).
+    /// Returns a handle to wait for the blinks to finish, or be notified when they do.
+    /// * `on_time` - Number of seconds on
+    /// * `off_time` - Number of seconds off
+    /// * `n` - Number of times to blink
+    /// * `background` - Whether to return immediately, blinking in the background
+    pub fn blink_with(
+        &mut self,
+        on_time: f32,
+        off_time: f32,
+        n: Option<i32>,
+        background: bool,
+    ) -> BlinkHandle {
+        let handle = self.blinker(on_time, off_time, n);
+        if !background {
+            handle.wait();
         }
+        handle
     }
     /// Set the number of times to blink the device
     /// * `n` - Number of times to blink
@@ -256,6 +312,14 @@ impl DigitalOutputDevice {
     }
 }
 
+impl Drop for DigitalOutputDevice {
+    /// Stops any blink pattern or source and turns the device off.
+    fn drop(&mut self) {
+        self.clear_source();
+        self.stop();
+    }
+}
+
 ///  Represents a light emitting diode (LED)
 ///
 /// # Example
@@ -270,19 +334,29 @@ impl DigitalOutputDevice {
 #[derive(Debug)]
 pub struct LED {
     device: Arc<Mutex<OutputDevice>>,
-    blinking: Arc<AtomicBool>,
-    handle: Option<JoinHandle<()>>,
+    handle: Option<TaskHandle>,
     blink_count: Option<i32>,
+    source: Mutex<Option<SourcePump>>,
+    source_delay: f32,
 }
 
 impl LED {
     pub fn new(pin: u8) -> LED {
-        LED {
-            device: Arc::new(Mutex::new(OutputDevice::new(pin))),
-            blinking: Arc::new(AtomicBool::new(false)),
+        match LED::try_new(pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<LED> {
+        Ok(LED {
+            device: Arc::new(Mutex::new(OutputDevice::try_new(pin)?)),
             handle: None,
             blink_count: None,
-        }
+            source: Mutex::new(None),
+            source_delay: DEFAULT_SOURCE_DELAY,
+        })
     }
 
     impl_digital_output_device!();
@@ -293,15 +367,61 @@ impl LED {
     }
 
     /// Make the device turn on and off repeatedly in the background.
-    /// Use `set_blink_count` to set the number of times to blink the device    
+    /// Use `set_blink_count` to set the number of times to blink the device.
+    /// Calling `on`, `off` or `blink` again, or dropping the LED, cancels the blinking.
     /// * `on_time` - Number of seconds on
     /// * `off_time` - Number of seconds off
     ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_gpiozero::pins::{self, MockFactory};
+    /// use rust_gpiozero::*;
+    /// use std::thread;
+    /// use std::time::Duration;
+    ///
+    /// let factory = MockFactory::new();
+    /// pins::set_default_factory(factory.clone());
+    ///
+    /// let mut led = LED::new(17);
+    /// led.blink(0.01, 0.01);
+    /// thread::sleep(Duration::from_millis(50));
+    ///
+    /// // The blinking is cancelled before the LED is turned on, and doesn't write to it again
+    /// led.on();
+    /// let states = factory.get(17).states();
+    /// thread::sleep(Duration::from_millis(50));
+    /// assert_eq!(factory.get(17).states(), states);
+    /// assert!(led.is_lit());
+    ///
+    /// // Nothing to wait for
+    /// led.wait();
+    /// ```
     pub fn blink(&mut self, on_time: f32, off_time: f32) {
-        match self.blink_count {
-            None => self.blinker(on_time, off_time, None),
-            Some(n) => self.blinker(on_time, off_time, Some(n)),
+        self.blinker(on_time, off_time, self.blink_count);
+    }
+
+    /// Make the device turn on and off `n` times, or endlessly if `n` is `None`, ignoring
+    /// the count set with `set_blink_count`. The device is off once all blinks are done.
+    /// If `background` is `false`, blocks until the blinks are done,
+    /// unless called from a callback on the scheduler thread (see `BlinkHandle::wait`).
+    /// Returns a handle to wait for the blinks to finish, or be notified when they do.
+    /// * `on_time` - Number of seconds on
+    /// * `off_time` - Number of seconds off
+    /// * `n` - Number of times to blink
+    /// * `background` - Whether to return immediately, blinking in the background
+    pub fn blink_with(
+        &mut self,
+        on_time: f32,
+        off_time: f32,
+        n: Option<i32>,
+        background: bool,
+    ) -> BlinkHandle {
+        let handle = self.blinker(on_time, off_time, n);
+        if !background {
+            handle.wait();
         }
+        handle
     }
     /// Set the number of times to blink the device    
     /// * `n` - Number of times to blink
@@ -310,6 +430,14 @@ impl LED {
     }
 }
 
+impl Drop for LED {
+    /// Stops any blink pattern or source and turns the device off.
+    fn drop(&mut self) {
+        self.clear_source();
+        self.stop();
+    }
+}
+
 /// Represents a digital buzzer component.
 ///
 /// Connect the cathode (negative pin) of the buzzer to a ground pin;
@@ -318,19 +446,29 @@ impl LED {
 #[derive(Debug)]
 pub struct Buzzer {
     device: Arc<Mutex<OutputDevice>>,
-    blinking: Arc<AtomicBool>,
-    handle: Option<JoinHandle<()>>,
+    handle: Option<TaskHandle>,
     blink_count: Option<i32>,
+    source: Mutex<Option<SourcePump>>,
+    source_delay: f32,
 }
 
 impl Buzzer {
     pub fn new(pin: u8) -> Buzzer {
-        Buzzer {
-            device: Arc::new(Mutex::new(OutputDevice::new(pin))),
-            blinking: Arc::new(AtomicBool::new(false)),
+        match Buzzer::try_new(pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<Buzzer> {
+        Ok(Buzzer {
+            device: Arc::new(Mutex::new(OutputDevice::try_new(pin)?)),
             handle: None,
             blink_count: None,
-        }
+            source: Mutex::new(None),
+            source_delay: DEFAULT_SOURCE_DELAY,
+        })
     }
 
     impl_digital_output_device!();
@@ -341,10 +479,30 @@ impl Buzzer {
     /// * `off_time` - Number of seconds off
     ///
     pub fn beep(&mut self, on_time: f32, off_time: f32) {
-        match self.blink_count {
-            None => self.blinker(on_time, off_time, None),
-            Some(n) => self.blinker(on_time, off_time, Some(n)),
+        self.blinker(on_time, off_time, self.blink_count);
+    }
+
+    /// Make the device beep `n` times, or endlessly if `n` is `None`, ignoring
+    /// the count set with `set_beep_count`. The device is off once all beeps are done.
+    /// If `background` is `false`, blocks until the beeps are done,
+    /// unless called from a callback on the scheduler thread (see `BlinkHandle::wait`).
+    /// Returns a handle to wait for the beeps to finish, or be notified when they do.
+    /// * `on_time` - Number of seconds on
+    /// * `off_time` - Number of seconds off
+    /// * `n` - Number of times to beep
+    /// * `background` - Whether to return immediately, beeping in the background
+    pub fn beep_with(
+        &mut self,
+        on_time: f32,
+        off_time: f32,
+        n: Option<i32>,
+        background: bool,
+    ) -> BlinkHandle {
+        let handle = self.blinker(on_time, off_time, n);
+        if !background {
+            handle.wait();
         }
+        handle
     }
     /// Set the number of times to beep the device    
     /// * `n` - Number of times to beep
@@ -353,30 +511,169 @@ impl Buzzer {
     }
 }
 
-/// Generic output device configured for software pulse-width modulation (PWM).
-/// The pulse width of the signal will be 100μs with a value range of [0,100] (where 0 is a constant low and 100 is a constant high) resulting in a frequenzy of 100 Hz.
+impl Drop for Buzzer {
+    /// Stops any blink pattern or source and turns the device off.
+    fn drop(&mut self) {
+        self.clear_source();
+        self.stop();
+    }
+}
+
+/// Selects how the PWM signal of a device is generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PwmMode {
+    /// Use a hardware PWM channel if the pin supports it, and software PWM otherwise.
+    /// On the Raspberry Pi, hardware PWM is available on BCM pins 12, 13, 18 and 19 once
+    /// the `pwm` or `pwm-2chan` overlay routes a channel to the pin. Pins 12 and 18 share
+    /// a channel, as do pins 13 and 19, so only the first device created on either pin of
+    /// a channel uses it.
+    Auto,
+    /// Use a hardware PWM channel, which keeps a steady signal regardless of the CPU load.
+    /// Creating the device fails with `Error::Unsupported` if the pin doesn't support it,
+    /// or `Error::PinBusy` with the other pin of the channel if it is already in use.
+    Hardware,
+    /// Use software PWM, which is available on every pin but jitters under CPU load.
+    Software,
+}
+
+/// Returns the pin for a PWM device, driven by hardware or software PWM according to `mode`.
+fn pwm_pin(pin: u8, mode: PwmMode) -> Result<Box<dyn OutputPin>> {
+    let factory = default_factory();
+    match mode {
+        PwmMode::Auto => factory.hardware_pwm(pin).or_else(|_| factory.output(pin)),
+        PwmMode::Hardware => factory.hardware_pwm(pin),
+        PwmMode::Software => factory.output(pin),
+    }
+}
+
+/// Generic output device configured for pulse-width modulation (PWM).
+/// Hardware PWM is used when the pin supports it, see `PwmMode`.
+/// The frequency of the signal defaults to 100 Hz and can be changed with `set_frequency`.
+#[derive(Debug)]
 pub struct PWMOutputDevice {
     device: Arc<Mutex<OutputDevice>>,
-    blinking: Arc<AtomicBool>,
-    handle: Option<JoinHandle<()>>,
+    handle: Option<TaskHandle>,
     blink_count: Option<i32>,
+    fader: Fader,
     active_state: bool,
     inactive_state: bool,
+    source: Mutex<Option<SourcePump>>,
+    source_delay: f32,
+}
+
+/// Sets the frequency of a PWM output, keeping its current value.
+fn write_pwm_frequency(
+    device: &Mutex<OutputDevice>,
+    active_high: bool,
+    frequency: f64,
+) -> Result<()> {
+    if !(frequency > 0.0 && frequency.is_finite()) {
+        return Err(Error::OutOfRange {
+            value: frequency,
+            min: 0.0,
+            max: f64::INFINITY,
+        });
+    }
+    let mut device = device.lock().unwrap();
+    device.pwm_frequency = frequency;
+    let value = device.pwm_value;
+    let duty_cycle = if active_high { value } else { 1.0 - value };
+    device.pin.set_pwm_frequency(frequency, duty_cycle)
+}
+
+/// Sets the duty cycle of a PWM output, inverting it for active low devices.
+fn write_pwm(device: &Mutex<OutputDevice>, active_high: bool, value: f64) -> Result<()> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(Error::OutOfRange {
+            value,
+            min: 0.0,
+            max: 1.0,
+        });
+    }
+    let duty_cycle = if active_high { value } else { 1.0 - value };
+    let mut device = device.lock().unwrap();
+    let frequency = device.pwm_frequency;
+    device.pin.set_pwm_frequency(frequency, duty_cycle)?;
+    device.pwm_value = value;
+    Ok(())
 }
 
 macro_rules! impl_pwm_device {
     () => {
         /// Set the duty cycle of the PWM device. 0.0 is off, 1.0 is fully on.
         /// Values in between may be specified for varying levels of power in the device.
-        pub fn set_value(&mut self, duty: f64) {
+        /// Returns `Error::OutOfRange` if `duty` is not between 0.0 and 1.0.
+        pub fn set_value(&mut self, duty: f64) -> Result<()> {
             self.write_state(duty)
         }
+
+        /// The duty cycle of the PWM device, between 0.0 (off) and 1.0 (fully on).
+        pub fn value(&self) -> f64 {
+            self.device.lock().unwrap().pwm_value
+        }
+
+        /// The frequency of the PWM signal in Hz. Defaults to 100 Hz.
+        pub fn frequency(&self) -> f64 {
+            self.device.lock().unwrap().pwm_frequency
+        }
+
+        /// Set the frequency of the PWM signal in Hz, keeping the current value.
+        /// The new frequency also applies to blinking, pulsing and values from a source.
+        /// Returns `Error::OutOfRange` if the frequency isn't positive.
+        pub fn set_frequency(&mut self, frequency: f64) -> Result<()> {
+            write_pwm_frequency(&self.device, self.active_high(), frequency)
+        }
+
+        /// Returns ``True`` if the device is currently active (its value is non-zero)
+        /// and ``False`` otherwise.
+        pub fn is_active(&self) -> bool {
+            self.value() != 0.0
+        }
+
+        /// Toggle the state of the device. If it's currently off (value is 0.0), this changes it
+        /// to "fully" on (value is 1.0). If the device has a duty cycle (value) of 0.1, this will
+        /// toggle it to 0.9, and so on.
+        pub fn toggle(&mut self) -> Result<()> {
+            let value = self.value();
+            self.write_state(1.0 - value)
+        }
         /// Set the number of times to blink the device
         /// * `n` - Number of times to blink
         pub fn set_blink_count(&mut self, n: i32) {
             self.blink_count = Some(n)
         }
 
+        /// The number of value changes per second while fading. Defaults to 25.
+        pub fn fade_fps(&self) -> f32 {
+            self.fader.fps
+        }
+
+        /// Set the number of value changes per second while fading.
+        /// Takes effect the next time `blink` or `pulse` is called.
+        /// Returns `Error::OutOfRange` if `fps` isn't positive.
+        pub fn set_fade_fps(&mut self, fps: f32) -> Result<()> {
+            if !(fps > 0.0 && fps.is_finite()) {
+                return Err(Error::OutOfRange {
+                    value: f64::from(fps),
+                    min: 0.0,
+                    max: f64::INFINITY,
+                });
+            }
+            self.fader.fps = fps;
+            Ok(())
+        }
+
+        /// The curve followed by the value while fading. Defaults to `Easing::Linear`.
+        pub fn easing(&self) -> Easing {
+            self.fader.easing
+        }
+
+        /// Set the curve followed by the value while fading.
+        /// Takes effect the next time `blink` or `pulse` is called.
+        pub fn set_easing(&mut self, easing: Easing) {
+            self.fader.easing = easing
+        }
+
         fn blinker(
             &mut self,
             on_time: f32,
@@ -384,101 +681,59 @@ macro_rules! impl_pwm_device {
             fade_in_time: f32,
             fade_out_time: f32,
             n: Option<i32>,
-        ) {
-            let mut sequence: Vec<(f32, f32)> = Vec::new();
-            let fps = 25.0;
-            // create sequence for fading in
-            if fade_in_time > 0.0 {
-                for i in 0..fps as i32 * fade_in_time as i32 {
-                    sequence.push((i as f32 * (1.0 / fps) / fade_in_time, 1.0 / fps))
-                }
-            }
-
-            // allow to stay on for on_time
-            sequence.push((1.0, on_time));
+        ) -> BlinkHandle {
+            let cycle = blink_cycle(on_time, off_time, fade_in_time, fade_out_time);
+            self.cancel();
+            let device = Arc::clone(&self.device);
+            let active_high = self.active_high();
+            let blink = self.fader.blink(cycle, n, move |value| {
+                write_pwm(&device, active_high, value)
+            });
+            let handle = scheduler::schedule(blink);
+            let completion = handle.completion();
+            self.handle = Some(handle);
+            completion
+        }
 
-            // create sequence for fading out
-            if fade_out_time > 0.0 {
-                for i in 0..fps as i32 * fade_out_time as i32 {
-                    sequence.push((1.0 - (i as f32 * (1.0 / fps) / fade_out_time), 1.0 / fps))
-                }
+        /// Cancels any blink pattern and source
+        fn cancel(&self) {
+            self.clear_source();
+            if let Some(handle) = &self.handle {
+                handle.cancel();
             }
-
-            // allow to stay off for off_time
-            sequence.push((0.0, off_time));
-
-            let device = Arc::clone(&self.device);
-            let blinking = Arc::clone(&self.blinking);
-
-            self.handle = Some(thread::spawn(move || {
-                blinking.store(true, Ordering::SeqCst);
-                match n {
-                    Some(end) => {
-                        for _ in 0..end {
-                            for (value, delay) in &sequence {
-                                if !blinking.load(Ordering::SeqCst) {
-                                    // device.lock().unwrap().off();
-                                    break;
-                                }
-                                device
-                                    .lock()
-                                    .unwrap()
-                                    .pin
-                                    .set_pwm_frequency(100.0, f64::from(*value))
-                                    .unwrap();
-                                thread::sleep(Duration::from_millis((delay * 1000 as f32) as u64));
-                            }
-                        }
-                    }
-                    None => loop {
-                        for (value, delay) in &sequence {
-                            if !blinking.load(Ordering::SeqCst) {
-                                // device.lock().unwrap().off();
-                                break;
-                            }
-                            device
-                                .lock()
-                                .unwrap()
-                                .pin
-                                .set_pwm_frequency(100.0, f64::from(*value))
-                                .unwrap();
-                            thread::sleep(Duration::from_millis((delay * 1000 as f32) as u64));
-                        }
-                    },
-                }
-            }));
         }
 
-        fn stop(&mut self) {
-            self.blinking.clone().store(false, Ordering::SeqCst);
-            if self.device.lock().unwrap().pin.clear_pwm().is_err() {
-                println!("Could not clear pwm for pin");
-            };
+        /// Cancels any blink pattern and source, and turns the PWM signal off.
+        fn stop(&mut self) -> Result<()> {
+            self.cancel();
+            let mut device = self.device.lock().unwrap();
+            device.pwm_value = 0.0;
+            device.pin.clear_pwm()
         }
 
-        fn write_state(&mut self, value: f64) {
+        fn write_state(&mut self, value: f64) -> Result<()> {
             if !(0.0..=1.0).contains(&value) {
-                println!("Value must be between 0.0 and 1.0");
-                return;
-            }
-            self.stop();
-            if self.active_high() {
-                self.device
-                    .lock()
-                    .unwrap()
-                    .pin
-                    .set_pwm_frequency(100.0, value)
-                    .unwrap()
-            } else {
-                self.device
-                    .lock()
-                    .unwrap()
-                    .pin
-                    .set_pwm_frequency(100.0, 1.0 - value)
-                    .unwrap()
+                return Err(Error::OutOfRange {
+                    value,
+                    min: 0.0,
+                    max: 1.0,
+                });
             }
+            // The PWM signal isn't cleared first, so that it changes without a glitch
+            self.cancel();
+            write_pwm(&self.device, self.active_high(), value)
+        }
+
+        /// Stops blinking and returns the function writing source values to the device.
+        fn source_writer(&mut self) -> impl FnMut(f64) -> Result<()> + Send + 'static {
+            self.cancel();
+            let device = Arc::clone(&self.device);
+            let active_high = self.active_high();
+            move |value| write_pwm(&device, active_high, value)
         }
 
+        impl_source!();
+
         /// Set the state for active_high
         pub fn set_active_high(&mut self, value: bool) {
             if value {
@@ -499,14 +754,22 @@ macro_rules! impl_pwm_device {
         }
 
         /// Turns the device on.
-        pub fn on(&mut self) {
+        pub fn on(&mut self) -> Result<()> {
             self.write_state(1.0)
         }
 
         /// Turns the device off.
-        pub fn off(&mut self) {
+        pub fn off(&mut self) -> Result<()> {
             self.write_state(0.0)
         }
+
+        /// Block until background process is done. Returns immediately if the device isn't
+        /// blinking, and never returns for endless patterns.
+        pub fn wait(&mut self) {
+            if let Some(handle) = &self.handle {
+                handle.completion().wait();
+            }
+        }
     };
 }
 
@@ -516,14 +779,42 @@ impl PWMOutputDevice {
     /// * `pin` - The GPIO pin which the device is attached to
     ///  
     pub fn new(pin: u8) -> PWMOutputDevice {
-        PWMOutputDevice {
-            device: Arc::new(Mutex::new(OutputDevice::new(pin))),
-            blinking: Arc::new(AtomicBool::new(false)),
+        match PWMOutputDevice::try_new(pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<PWMOutputDevice> {
+        PWMOutputDevice::try_new_with_pwm_mode(pin, PwmMode::Auto)
+    }
+
+    /// Returns a PWMOutputDevice with the pin number given, generating its PWM signal as
+    /// selected by `mode`
+    ///
+    /// * `pin` - The GPIO pin which the device is attached to
+    /// * `mode` - Whether to use hardware or software PWM
+    pub fn new_with_pwm_mode(pin: u8, mode: PwmMode) -> PWMOutputDevice {
+        match PWMOutputDevice::try_new_with_pwm_mode(pin, mode) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_with_pwm_mode`, but returns an error instead of panicking if the pin is not
+    /// available, or doesn't support the PWM mode requested
+    pub fn try_new_with_pwm_mode(pin: u8, mode: PwmMode) -> Result<PWMOutputDevice> {
+        Ok(PWMOutputDevice {
+            device: Arc::new(Mutex::new(OutputDevice::with_pin(pwm_pin(pin, mode)?))),
             handle: None,
             blink_count: None,
+            fader: Fader::default(),
             active_state: true,
             inactive_state: false,
-        }
+            source: Mutex::new(None),
+            source_delay: DEFAULT_SOURCE_DELAY,
+        })
     }
 
     impl_pwm_device!();
@@ -535,10 +826,40 @@ impl PWMOutputDevice {
     /// * `fade_out_time` - Number of seconds to spend fading out
     ///
     pub fn blink(&mut self, on_time: f32, off_time: f32, fade_in_time: f32, fade_out_time: f32) {
-        match self.blink_count {
-            None => self.blinker(on_time, off_time, fade_in_time, fade_out_time, None),
-            Some(n) => self.blinker(on_time, off_time, fade_in_time, fade_out_time, Some(n)),
+        self.blinker(
+            on_time,
+            off_time,
+            fade_in_time,
+            fade_out_time,
+            self.blink_count,
+        );
+    }
+
+    /// Make the device turn on and off `n` times, or endlessly if `n` is `None`, ignoring
+    /// the count set with `set_blink_count`. The device is off once all blinks are done.
+    /// If `background` is `false`, blocks until the blinks are done,
+    /// unless called from a callback on the scheduler thread (see `BlinkHandle::wait`).
+    /// Returns a handle to wait for the blinks to finish, or be notified when they do.
+    /// * `on_time` - Number of seconds on
+    /// * `off_time` - Number of seconds off
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    /// * `n` - Number of times to blink
+    /// * `background` - Whether to return immediately, blinking in the background
+    pub fn blink_with(
+        &mut self,
+        on_time: f32,
+        off_time: f32,
+        fade_in_time: f32,
+        fade_out_time: f32,
+        n: Option<i32>,
+        background: bool,
+    ) -> BlinkHandle {
+        let handle = self.blinker(on_time, off_time, fade_in_time, fade_out_time, n);
+        if !background {
+            handle.wait();
         }
+        handle
     }
 
     /// Make the device fade in and out repeatedly.    
@@ -548,12 +869,40 @@ impl PWMOutputDevice {
     pub fn pulse(&mut self, fade_in_time: f32, fade_out_time: f32) {
         self.blink(0.0, 0.0, fade_in_time, fade_out_time)
     }
+
+    /// Make the device fade in and out `n` times, or endlessly if `n` is `None`, ignoring
+    /// the count set with `set_blink_count`. The device is off once all pulses are done.
+    /// If `background` is `false`, blocks until the pulses are done,
+    /// unless called from a callback on the scheduler thread (see `BlinkHandle::wait`).
+    /// Returns a handle to wait for the pulses to finish, or be notified when they do.
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    /// * `n` - Number of times to pulse
+    /// * `background` - Whether to return immediately, pulsing in the background
+    pub fn pulse_with(
+        &mut self,
+        fade_in_time: f32,
+        fade_out_time: f32,
+        n: Option<i32>,
+        background: bool,
+    ) -> BlinkHandle {
+        self.blink_with(0.0, 0.0, fade_in_time, fade_out_time, n, background)
+    }
+}
+
+impl Drop for PWMOutputDevice {
+    /// Stops any blink pattern or source and turns the device off.
+    fn drop(&mut self) {
+        self.clear_source();
+        let _ = self.stop();
+    }
 }
 
 /// Represents a light emitting diode (LED) with variable brightness.
 /// A typical configuration of such a device is to connect a GPIO pin
 /// to the anode (long leg) of the LED, and the cathode (short leg) to ground,
 /// with an optional resistor to prevent the LED from burning out.
+#[derive(Debug)]
 pub struct PWMLED(PWMOutputDevice);
 
 impl PWMLED {
@@ -562,7 +911,32 @@ impl PWMLED {
     /// * `pin` - The GPIO pin which the device is attached to
     ///    
     pub fn new(pin: u8) -> PWMLED {
-        PWMLED(PWMOutputDevice::new(pin))
+        match PWMLED::try_new(pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<PWMLED> {
+        Ok(PWMLED(PWMOutputDevice::try_new(pin)?))
+    }
+
+    /// Returns a PWMLED with the pin number given, generating its PWM signal as selected by `mode`
+    ///
+    /// * `pin` - The GPIO pin which the device is attached to
+    /// * `mode` - Whether to use hardware or software PWM
+    pub fn new_with_pwm_mode(pin: u8, mode: PwmMode) -> PWMLED {
+        match PWMLED::try_new_with_pwm_mode(pin, mode) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_with_pwm_mode`, but returns an error instead of panicking if the pin is not
+    /// available, or doesn't support the PWM mode requested
+    pub fn try_new_with_pwm_mode(pin: u8, mode: PwmMode) -> Result<PWMLED> {
+        Ok(PWMLED(PWMOutputDevice::try_new_with_pwm_mode(pin, mode)?))
     }
 
     /// Make the device turn on and off repeatedly
@@ -575,14 +949,126 @@ impl PWMLED {
         self.0.blink(on_time, off_time, fade_in_time, fade_out_time)
     }
 
+    /// Make the device turn on and off `n` times, or endlessly if `n` is `None`, ignoring
+    /// the count set with `set_blink_count`. The LED is off once all blinks are done.
+    /// If `background` is `false`, blocks until the blinks are done,
+    /// unless called from a callback on the scheduler thread (see `BlinkHandle::wait`).
+    /// Returns a handle to wait for the blinks to finish, or be notified when they do.
+    /// * `on_time` - Number of seconds on
+    /// * `off_time` - Number of seconds off
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    /// * `n` - Number of times to blink
+    /// * `background` - Whether to return immediately, blinking in the background
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_gpiozero::pins::{self, MockFactory};
+    /// use rust_gpiozero::*;
+    /// use std::sync::mpsc;
+    /// use std::time::Duration;
+    ///
+    /// let factory = MockFactory::new();
+    /// pins::set_default_factory(factory.clone());
+    ///
+    /// let mut led = PWMLED::new(17);
+    /// // Blocks until both blinks are done
+    /// led.blink_with(0.01, 0.01, 0.0, 0.0, Some(2), false);
+    /// assert_eq!(factory.get(17).states(), vec![0.0, 1.0, 0.0, 1.0, 0.0]);
+    ///
+    /// // Get notified when blinking in the background is done
+    /// let (done, finished) = mpsc::channel();
+    /// let handle = led.blink_with(0.01, 0.01, 0.0, 0.0, Some(1), true);
+    /// handle.on_completed(move || done.send(()).unwrap());
+    /// finished.recv_timeout(Duration::from_secs(1)).unwrap();
+    /// assert!(handle.is_completed());
+    /// assert!(!led.is_lit());
+    /// ```
+    pub fn blink_with(
+        &mut self,
+        on_time: f32,
+        off_time: f32,
+        fade_in_time: f32,
+        fade_out_time: f32,
+        n: Option<i32>,
+        background: bool,
+    ) -> BlinkHandle {
+        self.0.blink_with(
+            on_time,
+            off_time,
+            fade_in_time,
+            fade_out_time,
+            n,
+            background,
+        )
+    }
+
     /// Turns the device on.
-    pub fn on(&mut self) {
-        self.0.on();
+    pub fn on(&mut self) -> Result<()> {
+        self.0.on()
     }
 
     /// Turns the device off.
-    pub fn off(&mut self) {
-        self.0.off();
+    pub fn off(&mut self) -> Result<()> {
+        self.0.off()
+    }
+
+    /// Toggle the state of the device. If it's currently off (value is 0.0), this changes it
+    /// to "fully" on (value is 1.0). If the device has a duty cycle (value) of 0.1, this will
+    /// toggle it to 0.9, and so on.
+    pub fn toggle(&mut self) -> Result<()> {
+        self.0.toggle()
+    }
+
+    /// The brightness of the LED, between 0.0 (off) and 1.0 (fully on).
+    pub fn value(&self) -> f64 {
+        self.0.value()
+    }
+
+    /// The frequency of the PWM signal in Hz. Defaults to 100 Hz.
+    pub fn frequency(&self) -> f64 {
+        self.0.frequency()
+    }
+
+    /// Set the frequency of the PWM signal in Hz, keeping the current brightness.
+    /// Raising it (e.g. to 1 kHz) avoids visible flicker on camera.
+    /// Returns `Error::OutOfRange` if the frequency isn't positive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_gpiozero::pins::{self, MockFactory};
+    /// use rust_gpiozero::*;
+    ///
+    /// let factory = MockFactory::new();
+    /// pins::set_default_factory(factory.clone());
+    ///
+    /// let mut led = PWMLED::new(17);
+    /// led.set_value(0.5).unwrap();
+    /// led.set_frequency(1000.0).unwrap();
+    /// assert_eq!(factory.get(17).frequency(), Some(1000.0));
+    /// assert_eq!(factory.get(17).value(), 0.5);
+    /// ```
+    pub fn set_frequency(&mut self, frequency: f64) -> Result<()> {
+        self.0.set_frequency(frequency)
+    }
+
+    /// Returns ``True`` if the device is currently active (its value is non-zero)
+    /// and ``False`` otherwise.
+    pub fn is_active(&self) -> bool {
+        self.0.is_active()
+    }
+
+    /// Returns ``True`` if the LED is currently lit and ``False`` otherwise.
+    pub fn is_lit(&self) -> bool {
+        self.0.is_active()
+    }
+
+    /// Block until background process is done. Returns immediately if the device isn't
+    /// blinking, and never returns for endless patterns.
+    pub fn wait(&mut self) {
+        self.0.wait()
     }
 
     /// Make the device fade in and out repeatedly.
@@ -593,10 +1079,31 @@ impl PWMLED {
         self.0.pulse(fade_in_time, fade_out_time);
     }
 
+    /// Make the device fade in and out `n` times, or endlessly if `n` is `None`, ignoring
+    /// the count set with `set_blink_count`. The LED is off once all pulses are done.
+    /// If `background` is `false`, blocks until the pulses are done,
+    /// unless called from a callback on the scheduler thread (see `BlinkHandle::wait`).
+    /// Returns a handle to wait for the pulses to finish, or be notified when they do.
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    /// * `n` - Number of times to pulse
+    /// * `background` - Whether to return immediately, pulsing in the background
+    pub fn pulse_with(
+        &mut self,
+        fade_in_time: f32,
+        fade_out_time: f32,
+        n: Option<i32>,
+        background: bool,
+    ) -> BlinkHandle {
+        self.0
+            .pulse_with(fade_in_time, fade_out_time, n, background)
+    }
+
     /// Set the duty cycle of the PWM device. 0.0 is off, 1.0 is fully on.
     /// Values in between may be specified for varying levels of power in the device.
-    pub fn set_value(&mut self, value: f64) {
-        self.0.set_value(value);
+    /// Returns `Error::OutOfRange` if `value` is not between 0.0 and 1.0.
+    pub fn set_value(&mut self, value: f64) -> Result<()> {
+        self.0.set_value(value)
     }
 
     /// Set the number of times to blink the device    
@@ -604,180 +1111,1261 @@ impl PWMLED {
     pub fn set_blink_count(&mut self, n: i32) {
         self.0.blink_count = Some(n)
     }
-}
 
-struct MotorCompositeDevice(PWMOutputDevice, PWMOutputDevice);
+    /// The number of brightness changes per second while fading. Defaults to 25.
+    pub fn fade_fps(&self) -> f32 {
+        self.0.fade_fps()
+    }
 
-///  Represents a generic motor connected
-///  to a bi-directional motor driver circuit (i.e. an H-bridge).
-///  Attach an H-bridge motor controller to your Pi; connect a power source (e.g. a battery pack or the 5V pin)
-///  to the controller; connect the outputs of the controller board to the two terminals of the motor; connect the inputs of the controller board to two GPIO pins.
-pub struct Motor {
-    devices: MotorCompositeDevice,
-    speed: f64,
-}
+    /// Set the number of brightness changes per second while fading.
+    /// Takes effect the next time `blink` or `pulse` is called.
+    /// Returns `Error::OutOfRange` if `fps` isn't positive.
+    pub fn set_fade_fps(&mut self, fps: f32) -> Result<()> {
+        self.0.set_fade_fps(fps)
+    }
 
-impl Motor {
-    /// creates a new Motor instance
-    /// * `forward_pin` - The GPIO pin that the forward input of the motor driver chip is connected to
-    /// * `backward` - The GPIO pin that the backward input of the motor driver chip is connected to
-    pub fn new(forward_pin: u8, backward_pin: u8) -> Motor {
-        let forward = PWMOutputDevice::new(forward_pin);
-        let backward = PWMOutputDevice::new(backward_pin);
-        Motor {
-            devices: MotorCompositeDevice(forward, backward),
-            speed: 1.0,
-        }
+    /// The curve followed by the brightness while fading. Defaults to `Easing::Linear`.
+    pub fn easing(&self) -> Easing {
+        self.0.easing()
     }
 
-    /// Drive the motor forwards at the current speed.
-    /// You can change the speed using `set_speed` before calling `forward`
-    pub fn forward(&mut self) {
-        self.devices.1.off();
-        self.devices.0.set_value(self.speed);
+    /// Set the curve followed by the brightness while fading.
+    /// Takes effect the next time `blink` or `pulse` is called.
+    /// `Easing::Gamma(2.2)` makes fades look even to the human eye.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_gpiozero::pins::{self, MockFactory};
+    /// use rust_gpiozero::*;
+    ///
+    /// let factory = MockFactory::new();
+    /// pins::set_default_factory(factory.clone());
+    ///
+    /// let mut led = PWMLED::new(17);
+    /// led.set_easing(Easing::Gamma(2.2));
+    /// led.set_fade_fps(50.0).unwrap();
+    /// led.set_blink_count(1);
+    /// led.pulse(0.1, 0.1);
+    /// led.wait();
+    ///
+    /// // Fractional fade times run 5 frames each way at 50 fps
+    /// let values = factory.get(17).states();
+    /// assert!(values.len() >= 11);
+    /// assert!(values.iter().all(|&value| (0.0..=1.0).contains(&value)));
+    /// assert!(values.contains(&Easing::Gamma(2.2).apply(0.2)));
+    /// assert_eq!(values.last(), Some(&0.0));
+    /// ```
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.0.set_easing(easing)
     }
 
-    /// Drive the motor backwards.
-    /// You can change the speed using `set_speed` before calling `backward`
-    pub fn backward(&mut self) {
-        self.devices.0.off();
-        self.devices.1.set_value(self.speed);
+    /// Continuously feed the values of `source` (e.g. the `values()` of another device)
+    /// into this device from a background thread, until the source is exhausted or
+    /// `clear_source` is called. Replaces any previously set source.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_gpiozero::pins::{self, MockFactory};
+    /// use rust_gpiozero::*;
+    /// use std::time::Duration;
+    ///
+    /// let factory = MockFactory::new();
+    /// pins::set_default_factory(factory.clone());
+    ///
+    /// let button = Button::new_with_pulldown(17);
+    /// let mut led = PWMLED::new(18);
+    /// led.set_source(button.values());
+    ///
+    /// factory.get(17).drive_high();
+    /// assert!(factory.get(18).wait_for_value(1.0, Duration::from_secs(1)));
+    /// factory.get(17).drive_low();
+    /// assert!(factory.get(18).wait_for_value(0.0, Duration::from_secs(1)));
+    /// led.clear_source();
+    /// ```
+    pub fn set_source<I>(&mut self, source: I)
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+        I::Item: Into<f64>,
+    {
+        self.0.set_source(source)
     }
 
-    /// Stop the motor.
-    pub fn stop(&mut self) {
-        self.devices.0.off();
-        self.devices.1.off();
+    /// Stop feeding values from the source set with `set_source`.
+    pub fn clear_source(&self) {
+        self.0.clear_source()
     }
 
-    /// The speed at which the motor should turn.
-    /// Can be any value between 0.0 (stopped) and the default 1.0 (maximum speed)
-    pub fn set_speed(&mut self, speed: f64) {
-        if !(0.0..=1.0).contains(&speed) {
-            println!("Speed must be between 0.0 and 1.0");
-            return;
-        }
-        self.speed = speed
+    /// The delay in seconds between two values read from the source. Defaults to 0.01.
+    pub fn source_delay(&self) -> f32 {
+        self.0.source_delay()
     }
-}
 
-/// Represents a PWM-controlled servo motor connected to a GPIO pin.
-//reference :https://github.com/golemparts/rppal/blob/master/examples/gpio_servo_softpwm.rs
-pub struct Servo {
-    pin: IoPin,
-    min_pulse_width: u64,
-    max_pulse_width: u64,
-    frame_width: u64,
+    /// Set the delay in seconds between two values read from the source.
+    /// Takes effect the next time `set_source` is called.
+    /// Returns `Error::OutOfRange` if the delay is negative or infinite.
+    pub fn set_source_delay(&mut self, seconds: f32) -> Result<()> {
+        self.0.set_source_delay(seconds)
+    }
 }
 
-impl Servo {
-    /// Returns a Servo with the pin number given with default `min_pulse_width` of 1ms,
-    /// `max_pulse_width` of 2ms and `frame_width` of 20ms
-    ///
-    /// * `pin` - The GPIO pin which the device is attached to
-    ///  
-    pub fn new(pin: u8) -> Servo {
-        match Gpio::new() {
-            Err(e) => panic!("{:?}", e),
-            Ok(gpio) => match gpio.get(pin) {
-                Err(e) => panic!("{:?}", e),
-                Ok(pin) => Servo {
-                    pin: pin.into_io(Mode::Output),
-                    min_pulse_width: 1000,
-                    max_pulse_width: 2000,
-                    frame_width: 20,
-                },
-            },
-        }
-    }
+/// Writes the colors of a blink pattern to the channels of an `RGBLED`
+type ColorWriter = Box<dyn FnMut((f64, f64, f64)) -> Result<()> + Send>;
 
-    /// Set the servo to its minimum position.
-    pub fn min(&mut self) {
-        if self
-            .pin
-            .set_pwm(
-                Duration::from_millis(self.frame_width),
-                Duration::from_micros(self.min_pulse_width),
-            )
-            .is_err()
-        {
-            println!("Failed to set servo to minimum position")
+/// The red, green and blue channels of an `RGBLED`
+#[derive(Debug)]
+enum RGBChannels {
+    /// PWM outputs, accepting any value between 0.0 and 1.0
+    Pwm([PWMOutputDevice; 3]),
+    /// Digital outputs, only accepting 0.0 and 1.0
+    Digital([DigitalOutputDevice; 3]),
+}
+
+impl RGBChannels {
+    fn is_pwm(&self) -> bool {
+        matches!(self, RGBChannels::Pwm(_))
+    }
+
+    fn check(&self, color: (f64, f64, f64)) -> Result<()> {
+        for &value in &[color.0, color.1, color.2] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(Error::OutOfRange {
+                    value,
+                    min: 0.0,
+                    max: 1.0,
+                });
+            }
+            if !self.is_pwm() && value != 0.0 && value != 1.0 {
+                return Err(Error::Unsupported("fractional colour values without PWM"));
+            }
         }
+        Ok(())
     }
 
-    /// Set the servo to its maximum position.
-    pub fn max(&mut self) {
-        if self
-            .pin
-            .set_pwm(
-                Duration::from_millis(self.frame_width),
-                Duration::from_micros(self.max_pulse_width),
-            )
-            .is_err()
-        {
-            println!("Failed to set servo to maximum position")
+    fn color(&self) -> (f64, f64, f64) {
+        let value = |channel: usize| match self {
+            RGBChannels::Pwm(devices) => devices[channel].value(),
+            RGBChannels::Digital(devices) if devices[channel].value() => 1.0,
+            RGBChannels::Digital(_) => 0.0,
+        };
+        (value(0), value(1), value(2))
+    }
+
+    fn write(&mut self, color: (f64, f64, f64)) -> Result<()> {
+        self.check(color)?;
+        let values = [color.0, color.1, color.2];
+        match self {
+            RGBChannels::Pwm(devices) => {
+                for (device, &value) in devices.iter_mut().zip(&values) {
+                    device.set_value(value)?;
+                }
+            }
+            RGBChannels::Digital(devices) => {
+                for (device, &value) in devices.iter().zip(&values) {
+                    if value == 1.0 {
+                        device.on();
+                    } else {
+                        device.off();
+                    }
+                }
+            }
         }
+        Ok(())
     }
 
-    /// Set the servo to its neutral position.
-    pub fn mid(&mut self) {
-        let mid_value = (self.min_pulse_width + self.max_pulse_width) / 2;
-        if self
-            .pin
-            .set_pwm(
-                Duration::from_millis(self.frame_width),
-                Duration::from_micros(mid_value),
-            )
-            .is_err()
-        {
-            println!("Failed to set servo to neutral position")
+    /// Returns the function writing the colors of a blink pattern to the channels, which
+    /// must have been checked.
+    fn writer(&self) -> ColorWriter {
+        match self {
+            RGBChannels::Pwm(devices) => {
+                let outputs: Vec<_> = devices
+                    .iter()
+                    .map(|device| (Arc::clone(&device.device), device.active_high()))
+                    .collect();
+                Box::new(move |color| {
+                    let values = [color.0, color.1, color.2];
+                    for ((device, active_high), &value) in outputs.iter().zip(&values) {
+                        write_pwm(device, *active_high, value)?;
+                    }
+                    Ok(())
+                })
+            }
+            RGBChannels::Digital(devices) => {
+                let outputs: Vec<_> = devices
+                    .iter()
+                    .map(|device| Arc::clone(&device.device))
+                    .collect();
+                Box::new(move |color| {
+                    let values = [color.0, color.1, color.2];
+                    for (device, &value) in outputs.iter().zip(&values) {
+                        let mut device = device.lock().unwrap();
+                        if value == 1.0 {
+                            device.on()
+                        } else {
+                            device.off()
+                        }
+                    }
+                    Ok(())
+                })
+            }
         }
     }
 
-    /// Set servo to any position between min and max.
-    /// value must be between -1 (the minimun position) and +1 (the maximum position).
-    pub fn set_position(&mut self, value: f64) {
-        if value >= -1.0 && value <= 1.0 {
-            // Map value form [-1, 1] to [min_pulse_width, max_pulse_width] linearly
-            let range: f64 = (self.max_pulse_width - self.min_pulse_width) as f64;
-            let pulse_width: u64 = self.min_pulse_width + (((value + 1.0)/2.0) * range).round() as u64;
-            if self
-                .pin
-                .set_pwm(
-                    Duration::from_millis(self.frame_width),
-                    Duration::from_micros(pulse_width),
+    fn active_high(&self) -> bool {
+        match self {
+            RGBChannels::Pwm(devices) => devices[0].active_high(),
+            RGBChannels::Digital(devices) => devices[0].active_high(),
+        }
+    }
+
+    fn set_active_high(&mut self, value: bool) {
+        match self {
+            RGBChannels::Pwm(devices) => {
+                for device in devices {
+                    device.set_active_high(value);
+                }
+            }
+            RGBChannels::Digital(devices) => {
+                for device in devices {
+                    device.set_active_high(value);
+                }
+            }
+        }
+    }
+}
+
+/// Represents a full color LED component (composed of red, green, and blue LEDs).
+/// Connect the common cathode (longest leg) to a ground pin; connect each of
+/// the other legs (representing the red, green, and blue anodes) to any GPIO pins.
+/// For a common anode LED, connect the common leg to 3V3 and use `set_active_high(false)`.
+///
+/// Each color is a `(red, green, blue)` tuple of values between 0.0 and 1.0.
+/// By default each channel is a `PWMOutputDevice`, using hardware PWM when the pin supports it
+/// (see `PwmMode`); use `new_without_pwm` to drive them as `DigitalOutputDevice`s instead,
+/// which only accepts 0.0 and 1.0.
+///
+/// # Example
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut led = RGBLED::new(17, 18, 19);
+/// led.set_color((1.0, 0.5, 0.0)).unwrap();
+/// assert_eq!(factory.get(18).value(), 0.5);
+///
+/// led.set_active_high(false);
+/// led.set_blue(1.0).unwrap();
+/// assert_eq!(factory.get(17).value(), 0.0);
+/// assert_eq!(factory.get(19).value(), 0.0);
+/// assert_eq!(led.color(), (1.0, 0.5, 1.0));
+/// ```
+#[derive(Debug)]
+pub struct RGBLED {
+    channels: RGBChannels,
+    handle: Option<TaskHandle>,
+    blink_count: Option<i32>,
+    fader: Fader,
+}
+
+impl RGBLED {
+    /// Returns an RGBLED with the pin numbers given, using PWM on each channel
+    ///
+    /// * `red` - The GPIO pin that controls the red component of the RGB LED
+    /// * `green` - The GPIO pin that controls the green component of the RGB LED
+    /// * `blue` - The GPIO pin that controls the blue component of the RGB LED
+    pub fn new(red: u8, green: u8, blue: u8) -> RGBLED {
+        match RGBLED::try_new(red, green, blue) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new(red: u8, green: u8, blue: u8) -> Result<RGBLED> {
+        RGBLED::try_new_with_pwm_mode(red, green, blue, PwmMode::Auto)
+    }
+
+    /// Returns an RGBLED with the pin numbers given, generating the PWM signal of each
+    /// channel as selected by `mode`
+    ///
+    /// * `red` - The GPIO pin that controls the red component of the RGB LED
+    /// * `green` - The GPIO pin that controls the green component of the RGB LED
+    /// * `blue` - The GPIO pin that controls the blue component of the RGB LED
+    /// * `mode` - Whether to use hardware or software PWM
+    pub fn new_with_pwm_mode(red: u8, green: u8, blue: u8, mode: PwmMode) -> RGBLED {
+        match RGBLED::try_new_with_pwm_mode(red, green, blue, mode) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_with_pwm_mode`, but returns an error instead of panicking if a pin is not
+    /// available, or doesn't support the PWM mode requested
+    pub fn try_new_with_pwm_mode(red: u8, green: u8, blue: u8, mode: PwmMode) -> Result<RGBLED> {
+        RGBLED::with_channels(RGBChannels::Pwm([
+            PWMOutputDevice::try_new_with_pwm_mode(red, mode)?,
+            PWMOutputDevice::try_new_with_pwm_mode(green, mode)?,
+            PWMOutputDevice::try_new_with_pwm_mode(blue, mode)?,
+        ]))
+    }
+
+    /// Returns an RGBLED with the pin numbers given, driving each channel as a digital output.
+    /// Only the values 0.0 and 1.0 can be used for each channel, giving 8 possible colors.
+    pub fn new_without_pwm(red: u8, green: u8, blue: u8) -> RGBLED {
+        match RGBLED::try_new_without_pwm(red, green, blue) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_without_pwm`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new_without_pwm(red: u8, green: u8, blue: u8) -> Result<RGBLED> {
+        RGBLED::with_channels(RGBChannels::Digital([
+            DigitalOutputDevice::try_new(red)?,
+            DigitalOutputDevice::try_new(green)?,
+            DigitalOutputDevice::try_new(blue)?,
+        ]))
+    }
+
+    fn with_channels(mut channels: RGBChannels) -> Result<RGBLED> {
+        channels.write((0.0, 0.0, 0.0))?;
+        Ok(RGBLED {
+            channels,
+            handle: None,
+            blink_count: None,
+            fader: Fader::default(),
+        })
+    }
+
+    /// Cancels any blink pattern, leaving the current color.
+    fn stop(&self) {
+        if let Some(handle) = &self.handle {
+            handle.cancel();
+        }
+    }
+
+    /// The color of the LED as a `(red, green, blue)` tuple
+    pub fn color(&self) -> (f64, f64, f64) {
+        self.channels.color()
+    }
+
+    /// Set the color of the LED. Returns `Error::OutOfRange` if a value is not between
+    /// 0.0 and 1.0, or `Error::Unsupported` for fractional values without PWM.
+    pub fn set_color(&mut self, color: (f64, f64, f64)) -> Result<()> {
+        self.channels.check(color)?;
+        self.stop();
+        self.channels.write(color)
+    }
+
+    /// The red component of the color
+    pub fn red(&self) -> f64 {
+        self.color().0
+    }
+
+    /// Set the red component of the color, keeping the other components
+    pub fn set_red(&mut self, value: f64) -> Result<()> {
+        let (_, green, blue) = self.color();
+        self.set_color((value, green, blue))
+    }
+
+    /// The green component of the color
+    pub fn green(&self) -> f64 {
+        self.color().1
+    }
+
+    /// Set the green component of the color, keeping the other components
+    pub fn set_green(&mut self, value: f64) -> Result<()> {
+        let (red, _, blue) = self.color();
+        self.set_color((red, value, blue))
+    }
+
+    /// The blue component of the color
+    pub fn blue(&self) -> f64 {
+        self.color().2
+    }
+
+    /// Set the blue component of the color, keeping the other components
+    pub fn set_blue(&mut self, value: f64) -> Result<()> {
+        let (red, green, _) = self.color();
+        self.set_color((red, green, value))
+    }
+
+    /// Returns ``True`` if the LED is currently active (not black) and ``False`` otherwise.
+    pub fn is_active(&self) -> bool {
+        self.color() != (0.0, 0.0, 0.0)
+    }
+
+    /// Returns ``True`` if the LED is currently active (not black) and ``False`` otherwise.
+    pub fn is_lit(&self) -> bool {
+        self.is_active()
+    }
+
+    /// Turn the LED on. This equivalent to setting the color to `(1.0, 1.0, 1.0)`.
+    pub fn on(&mut self) -> Result<()> {
+        self.set_color((1.0, 1.0, 1.0))
+    }
+
+    /// Turn the LED off. This is equivalent to setting the color to `(0.0, 0.0, 0.0)`.
+    pub fn off(&mut self) -> Result<()> {
+        self.set_color((0.0, 0.0, 0.0))
+    }
+
+    /// Toggle the state of the device. If the device is currently off, it is turned on,
+    /// with each channel set to the inverse of its current value.
+    pub fn toggle(&mut self) -> Result<()> {
+        let (red, green, blue) = self.color();
+        self.set_color((1.0 - red, 1.0 - green, 1.0 - blue))
+    }
+
+    /// When ``True``, the channels are driven high to light the LED (common cathode).
+    /// When ``False``, they are driven low to light it (common anode).
+    pub fn active_high(&self) -> bool {
+        self.channels.active_high()
+    }
+
+    /// Set the state for active_high, and rewrite the current color to the pins accordingly.
+    /// Stops any blink pattern, which would keep driving the pins the previous way.
+    pub fn set_active_high(&mut self, value: bool) {
+        self.stop();
+        let color = self.color();
+        self.channels.set_active_high(value);
+        // The current color has already been validated
+        let _ = self.channels.write(color);
+    }
+
+    /// Set the number of times to blink the device
+    /// * `n` - Number of times to blink
+    pub fn set_blink_count(&mut self, n: i32) {
+        self.blink_count = Some(n)
+    }
+
+    /// The number of color changes per second while fading. Defaults to 25.
+    pub fn fade_fps(&self) -> f32 {
+        self.fader.fps
+    }
+
+    /// Set the number of color changes per second while fading.
+    /// Takes effect the next time `blink` or `pulse` is called.
+    /// Returns `Error::OutOfRange` if `fps` isn't positive.
+    pub fn set_fade_fps(&mut self, fps: f32) -> Result<()> {
+        if !(fps > 0.0 && fps.is_finite()) {
+            return Err(Error::OutOfRange {
+                value: f64::from(fps),
+                min: 0.0,
+                max: f64::INFINITY,
+            });
+        }
+        self.fader.fps = fps;
+        Ok(())
+    }
+
+    /// The curve followed by the color while fading, from `off_color` to `on_color`.
+    /// Defaults to `Easing::Linear`.
+    pub fn easing(&self) -> Easing {
+        self.fader.easing
+    }
+
+    /// Set the curve followed by the color while fading.
+    /// Takes effect the next time `blink` or `pulse` is called.
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.fader.easing = easing
+    }
+
+    /// Make the device turn on and off repeatedly in the background.
+    /// Use `set_blink_count` to set the number of times to blink the device.
+    /// Returns an error if a color is invalid, or if fade times are given without PWM.
+    /// * `on_time` - Number of seconds on
+    /// * `off_time` - Number of seconds off
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    /// * `on_color` - The color to use when the LED is "on"
+    /// * `off_color` - The color to use when the LED is "off"
+    pub fn blink(
+        &mut self,
+        on_time: f32,
+        off_time: f32,
+        fade_in_time: f32,
+        fade_out_time: f32,
+        on_color: (f64, f64, f64),
+        off_color: (f64, f64, f64),
+    ) -> Result<()> {
+        if !self.channels.is_pwm() && (fade_in_time > 0.0 || fade_out_time > 0.0) {
+            return Err(Error::Unsupported("fading without PWM"));
+        }
+        self.channels.check(on_color)?;
+        self.channels.check(off_color)?;
+        self.stop();
+
+        let cycle = blink_cycle(on_time, off_time, fade_in_time, fade_out_time);
+        let mut write = self.channels.writer();
+        let blink = self.fader.blink(cycle, self.blink_count, move |level| {
+            let color = if level >= 1.0 {
+                on_color
+            } else {
+                (
+                    off_color.0 + (on_color.0 - off_color.0) * level,
+                    off_color.1 + (on_color.1 - off_color.1) * level,
+                    off_color.2 + (on_color.2 - off_color.2) * level,
                 )
-                .is_err()
-            {
-                println!("Failed to set servo to a new position");
+            };
+            write(color)
+        });
+        self.handle = Some(scheduler::schedule(blink));
+        Ok(())
+    }
+
+    /// Make the device fade in and out repeatedly in the background.
+    /// Returns `Error::Unsupported` without PWM.
+    /// * `fade_in_time` - Number of seconds to spend fading in
+    /// * `fade_out_time` - Number of seconds to spend fading out
+    /// * `on_color` - The color to fade in to
+    /// * `off_color` - The color to fade out to
+    pub fn pulse(
+        &mut self,
+        fade_in_time: f32,
+        fade_out_time: f32,
+        on_color: (f64, f64, f64),
+        off_color: (f64, f64, f64),
+    ) -> Result<()> {
+        if !self.channels.is_pwm() {
+            return Err(Error::Unsupported("pulse without PWM"));
+        }
+        self.blink(0.0, 0.0, fade_in_time, fade_out_time, on_color, off_color)
+    }
+
+    /// Block until background process is done. Returns immediately if the device isn't
+    /// blinking, and never returns for endless patterns.
+    pub fn wait(&mut self) {
+        if let Some(handle) = &self.handle {
+            handle.completion().wait();
+        }
+    }
+
+    /// Shut down the device and release all associated resources.
+    pub fn close(self) {
+        drop(self)
+    }
+}
+
+impl Drop for RGBLED {
+    /// Stops any blink pattern and turns the LED off, before the channels are dropped.
+    fn drop(&mut self) {
+        self.stop();
+        let _ = self.channels.write((0.0, 0.0, 0.0));
+    }
+}
+
+/// The inputs of a motor driver circuit
+#[derive(Debug)]
+enum MotorDriver {
+    /// A PWM input for each direction
+    ForwardBackward(PWMOutputDevice, PWMOutputDevice),
+    /// A digital input selecting the direction (phase) and a PWM input setting the speed (enable)
+    PhaseEnable(DigitalOutputDevice, PWMOutputDevice),
+}
+
+/// An output held high while the motor exists, to enable its driver or take it out of standby
+#[derive(Debug)]
+struct MotorEnable(OutputDevice);
+
+impl MotorEnable {
+    fn try_new(pin: u8) -> Result<MotorEnable> {
+        let mut device = OutputDevice::try_new(pin)?;
+        device.on();
+        Ok(MotorEnable(device))
+    }
+}
+
+impl Drop for MotorEnable {
+    fn drop(&mut self) {
+        self.0.off()
+    }
+}
+
+///  Represents a generic motor connected
+///  to a bi-directional motor driver circuit (i.e. an H-bridge).
+///  Attach an H-bridge motor controller to your Pi; connect a power source (e.g. a battery pack or the 5V pin)
+///  to the controller; connect the outputs of the controller board to the two terminals of the motor; connect the inputs of the controller board to two GPIO pins.
+#[derive(Debug)]
+pub struct Motor {
+    devices: MotorDriver,
+    // Only held to keep the driver enabled until the motor is dropped
+    _enable: Option<MotorEnable>,
+    speed: f64,
+    acceleration: Option<f64>,
+    ramp: Option<SourcePump>,
+    source: Mutex<Option<SourcePump>>,
+    source_delay: f32,
+}
+
+/// Interval in seconds between two speed changes while a motor ramps to a new speed
+const RAMP_INTERVAL: f64 = 0.02;
+
+impl Motor {
+    /// creates a new Motor instance
+    /// * `forward_pin` - The GPIO pin that the forward input of the motor driver chip is connected to
+    /// * `backward` - The GPIO pin that the backward input of the motor driver chip is connected to
+    pub fn new(forward_pin: u8, backward_pin: u8) -> Motor {
+        match Motor::try_new(forward_pin, backward_pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if either pin is not available
+    pub fn try_new(forward_pin: u8, backward_pin: u8) -> Result<Motor> {
+        let forward = PWMOutputDevice::try_new(forward_pin)?;
+        let backward = PWMOutputDevice::try_new(backward_pin)?;
+        Ok(Motor::with_driver(
+            MotorDriver::ForwardBackward(forward, backward),
+            None,
+        ))
+    }
+
+    /// creates a new Motor instance whose driver chip has an enable pin.
+    /// The enable pin is driven high while the motor exists, and low when it is dropped.
+    /// * `forward_pin` - The GPIO pin that the forward input of the motor driver chip is connected to
+    /// * `backward_pin` - The GPIO pin that the backward input of the motor driver chip is connected to
+    /// * `enable_pin` - The GPIO pin that the enable input of the motor driver chip is connected to
+    pub fn new_with_enable(forward_pin: u8, backward_pin: u8, enable_pin: u8) -> Motor {
+        match Motor::try_new_with_enable(forward_pin, backward_pin, enable_pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_with_enable`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new_with_enable(forward_pin: u8, backward_pin: u8, enable_pin: u8) -> Result<Motor> {
+        let forward = PWMOutputDevice::try_new(forward_pin)?;
+        let backward = PWMOutputDevice::try_new(backward_pin)?;
+        Ok(Motor::with_driver(
+            MotorDriver::ForwardBackward(forward, backward),
+            Some(MotorEnable::try_new(enable_pin)?),
+        ))
+    }
+
+    fn with_driver(devices: MotorDriver, enable: Option<MotorEnable>) -> Motor {
+        Motor {
+            devices,
+            _enable: enable,
+            speed: 1.0,
+            acceleration: None,
+            ramp: None,
+            source: Mutex::new(None),
+            source_delay: DEFAULT_SOURCE_DELAY,
+        }
+    }
+
+    /// Drive the motor forwards at the current speed.
+    /// You can change the speed using `set_speed` before calling `forward`
+    pub fn forward(&mut self) -> Result<()> {
+        self.ramp_to(self.speed)
+    }
+
+    /// Drive the motor backwards.
+    /// You can change the speed using `set_speed` before calling `backward`
+    pub fn backward(&mut self) -> Result<()> {
+        self.ramp_to(-self.speed)
+    }
+
+    /// Reverse the current direction of the motor, keeping its speed.
+    /// If the motor is stopped it remains stopped.
+    pub fn reverse(&mut self) -> Result<()> {
+        let value = self.value();
+        self.ramp_to(-value)
+    }
+
+    /// Stop the motor immediately, even if an acceleration is set.
+    /// Also stops feeding values from the source set with `set_source`.
+    pub fn stop(&mut self) -> Result<()> {
+        self.clear_source();
+        self.stop_ramp();
+        match &mut self.devices {
+            MotorDriver::ForwardBackward(forward, backward) => {
+                forward.off()?;
+                backward.off()
+            }
+            MotorDriver::PhaseEnable(_, enable) => enable.off(),
+        }
+    }
+
+    /// The speed at which the motor should turn.
+    /// Can be any value between 0.0 (stopped) and the default 1.0 (maximum speed).
+    /// Returns `Error::OutOfRange` for any other value.
+    pub fn set_speed(&mut self, speed: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&speed) {
+            return Err(Error::OutOfRange {
+                value: speed,
+                min: 0.0,
+                max: 1.0,
+            });
+        }
+        self.speed = speed;
+        Ok(())
+    }
+
+    /// The frequency of the PWM signal driving the motor in Hz. Defaults to 100 Hz.
+    pub fn frequency(&self) -> f64 {
+        match &self.devices {
+            MotorDriver::ForwardBackward(forward, _) => forward.frequency(),
+            MotorDriver::PhaseEnable(_, enable) => enable.frequency(),
+        }
+    }
+
+    /// Set the frequency of the PWM signal driving the motor in Hz, keeping the current speed.
+    /// Motor drivers often need a frequency between 1 and 20 kHz.
+    /// Returns `Error::OutOfRange` if the frequency isn't positive.
+    pub fn set_frequency(&mut self, frequency: f64) -> Result<()> {
+        match &mut self.devices {
+            MotorDriver::ForwardBackward(forward, backward) => {
+                forward.set_frequency(frequency)?;
+                backward.set_frequency(frequency)
+            }
+            MotorDriver::PhaseEnable(_, enable) => enable.set_frequency(frequency),
+        }
+    }
+
+    /// The current speed of the motor, between -1.0 (full speed backward)
+    /// and 1.0 (full speed forward).
+    pub fn value(&self) -> f64 {
+        match &self.devices {
+            MotorDriver::ForwardBackward(forward, backward) => forward.value() - backward.value(),
+            MotorDriver::PhaseEnable(phase, enable) if phase.value() => -enable.value(),
+            MotorDriver::PhaseEnable(_, enable) => enable.value(),
+        }
+    }
+
+    /// Drive the motor at the given speed, between -1.0 (full speed backward)
+    /// and 1.0 (full speed forward). The sign of the value selects the direction.
+    /// Returns `Error::OutOfRange` for any other value.
+    /// Stops feeding values from the source set with `set_source`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_gpiozero::pins::{self, MockFactory};
+    /// use rust_gpiozero::*;
+    /// use std::time::Duration;
+    ///
+    /// let factory = MockFactory::new();
+    /// pins::set_default_factory(factory.clone());
+    ///
+    /// let mut motor = Motor::new(17, 18);
+    /// motor.set_value(-0.5).unwrap();
+    /// assert_eq!(factory.get(18).value(), 0.5);
+    /// assert!(motor.is_active());
+    ///
+    /// // Ramp up to full speed forward, changing speed by 10.0 per second
+    /// motor.set_acceleration(Some(10.0)).unwrap();
+    /// motor.set_value(1.0).unwrap();
+    /// assert!(motor.value() < 1.0);
+    /// assert!(factory.get(17).wait_for_value(1.0, Duration::from_secs(1)));
+    /// assert_eq!(motor.value(), 1.0);
+    /// ```
+    pub fn set_value(&mut self, value: f64) -> Result<()> {
+        check_motor_value(value)?;
+        self.ramp_to(value)
+    }
+
+    /// Returns ``True`` if the motor is currently running and ``False`` otherwise.
+    pub fn is_active(&self) -> bool {
+        self.value() != 0.0
+    }
+
+    /// The maximum rate of change of the motor's speed, in speed units per second,
+    /// or `None` (the default) to change speed immediately.
+    pub fn acceleration(&self) -> Option<f64> {
+        self.acceleration
+    }
+
+    /// Limit the rate of change of the motor's speed, so that starting, stopping and changing
+    /// direction doesn't draw a sudden current. With an acceleration of 2.0, the motor takes
+    /// 0.5 seconds to go from stopped to full speed, and a second to reverse from full speed.
+    /// New speeds are then reached gradually in the background; `stop` and values from a
+    /// source still apply immediately. Returns `Error::OutOfRange` if the acceleration
+    /// isn't positive.
+    pub fn set_acceleration(&mut self, acceleration: Option<f64>) -> Result<()> {
+        if let Some(value) = acceleration {
+            if value <= 0.0 || value.is_nan() {
+                return Err(Error::OutOfRange {
+                    value,
+                    min: 0.0,
+                    max: f64::INFINITY,
+                });
+            }
+        }
+        self.acceleration = acceleration;
+        Ok(())
+    }
+
+    /// Changes the speed of the motor, gradually if an acceleration is set
+    fn ramp_to(&mut self, target: f64) -> Result<()> {
+        self.clear_source();
+        self.stop_ramp();
+        let acceleration = match self.acceleration {
+            None => return self.drive(target),
+            Some(acceleration) => acceleration,
+        };
+        let start = self.value();
+        if target == start {
+            return Ok(());
+        }
+        let step = acceleration * RAMP_INTERVAL * (target - start).signum();
+        // At least one step, as a huge acceleration takes less than a step to reach the target
+        let steps = ((target - start) / step).ceil().max(1.0) as usize;
+        let values = (1..=steps).map(move |i| {
+            if i == steps {
+                target
+            } else {
+                start + step * i as f64
+            }
+        });
+        let mut write = self.source_writer();
+        // Apply the first step now, so that the motor reacts before this returns
+        let mut values = values.peekable();
+        if let Some(value) = values.next() {
+            write(value)?;
+        }
+        if values.peek().is_some() {
+            self.ramp = Some(SourcePump::start(values, RAMP_INTERVAL as f32, write));
+        }
+        Ok(())
+    }
+
+    fn stop_ramp(&mut self) {
+        if let Some(mut ramp) = self.ramp.take() {
+            ramp.stop();
+        }
+    }
+
+    /// Drives the motor at a speed already checked to be between -1.0 and 1.0
+    fn drive(&mut self, value: f64) -> Result<()> {
+        match &mut self.devices {
+            MotorDriver::ForwardBackward(forward, backward) => {
+                if value > 0.0 {
+                    backward.off()?;
+                    forward.set_value(value)
+                } else {
+                    forward.off()?;
+                    backward.set_value(-value)
+                }
+            }
+            MotorDriver::PhaseEnable(phase, enable) => {
+                if value > 0.0 {
+                    phase.off();
+                } else if value < 0.0 {
+                    phase.on();
+                }
+                enable.set_value(value.abs())
+            }
+        }
+    }
+
+    /// Stops ramping and any source, and returns the function writing source values to the motor.
+    /// Values between -1.0 (full speed backward) and 1.0 (full speed forward) set both the
+    /// direction and the speed.
+    pub(crate) fn source_writer(&mut self) -> Box<dyn FnMut(f64) -> Result<()> + Send> {
+        self.clear_source();
+        self.stop_ramp();
+        match &self.devices {
+            MotorDriver::ForwardBackward(forward, backward) => {
+                let forward_active_high = forward.active_high();
+                let forward = Arc::clone(&forward.device);
+                let backward_active_high = backward.active_high();
+                let backward = Arc::clone(&backward.device);
+                Box::new(move |value| {
+                    check_motor_value(value)?;
+                    if value > 0.0 {
+                        write_pwm(&backward, backward_active_high, 0.0)?;
+                        write_pwm(&forward, forward_active_high, value)
+                    } else {
+                        write_pwm(&forward, forward_active_high, 0.0)?;
+                        write_pwm(&backward, backward_active_high, -value)
+                    }
+                })
+            }
+            MotorDriver::PhaseEnable(phase, enable) => {
+                let phase = Arc::clone(&phase.device);
+                let enable_active_high = enable.active_high();
+                let enable = Arc::clone(&enable.device);
+                Box::new(move |value| {
+                    check_motor_value(value)?;
+                    if value > 0.0 {
+                        phase.lock().unwrap().off();
+                    } else if value < 0.0 {
+                        phase.lock().unwrap().on();
+                    }
+                    write_pwm(&enable, enable_active_high, value.abs())
+                })
             }
         }
-        else {
-            println!("set_position value must be between -1 and 1");
+    }
+
+    impl_source!();
+}
+
+impl Drop for Motor {
+    /// Stops any ramp or source before stopping the motor, so that they can't drive it again.
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Checks that a motor speed is between -1.0 (full speed backward) and 1.0 (full speed forward)
+fn check_motor_value(value: f64) -> Result<()> {
+    if !(-1.0..=1.0).contains(&value) {
+        return Err(Error::OutOfRange {
+            value,
+            min: -1.0,
+            max: 1.0,
+        });
+    }
+    Ok(())
+}
+
+/// Represents a generic motor connected to a Phase/Enable motor driver circuit;
+/// the phase of the driver controls whether the motor turns forwards or backwards,
+/// while enable controls the speed with PWM (e.g. the DRV8835 in phase/enable mode).
+///
+/// # Example
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut motor = PhaseEnableMotor::new_with_standby(5, 12, 6);
+/// assert_eq!(factory.get(6).value(), 1.0);
+///
+/// motor.set_speed(0.5).unwrap();
+/// motor.backward().unwrap();
+/// assert_eq!(factory.get(5).value(), 1.0);
+/// assert_eq!(factory.get(12).value(), 0.5);
+///
+/// motor.reverse().unwrap();
+/// assert_eq!(factory.get(5).value(), 0.0);
+/// assert_eq!(factory.get(12).value(), 0.5);
+///
+/// drop(motor);
+/// assert_eq!(factory.get(6).value(), 0.0);
+/// ```
+#[derive(Debug)]
+pub struct PhaseEnableMotor(Motor);
+
+impl PhaseEnableMotor {
+    /// creates a new PhaseEnableMotor instance
+    /// * `phase_pin` - The GPIO pin that the phase (direction) input of the motor driver chip is connected to
+    /// * `enable_pin` - The GPIO pin that the enable (speed) input of the motor driver chip is connected to
+    pub fn new(phase_pin: u8, enable_pin: u8) -> PhaseEnableMotor {
+        match PhaseEnableMotor::try_new(phase_pin, enable_pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if either pin is not available
+    pub fn try_new(phase_pin: u8, enable_pin: u8) -> Result<PhaseEnableMotor> {
+        let phase = DigitalOutputDevice::try_new(phase_pin)?;
+        let enable = PWMOutputDevice::try_new(enable_pin)?;
+        Ok(PhaseEnableMotor(Motor::with_driver(
+            MotorDriver::PhaseEnable(phase, enable),
+            None,
+        )))
+    }
+
+    /// creates a new PhaseEnableMotor instance whose driver chip has a standby pin.
+    /// The standby pin is driven high while the motor exists, and low when it is dropped.
+    /// * `phase_pin` - The GPIO pin that the phase (direction) input of the motor driver chip is connected to
+    /// * `enable_pin` - The GPIO pin that the enable (speed) input of the motor driver chip is connected to
+    /// * `standby_pin` - The GPIO pin that the standby input of the motor driver chip is connected to
+    pub fn new_with_standby(phase_pin: u8, enable_pin: u8, standby_pin: u8) -> PhaseEnableMotor {
+        match PhaseEnableMotor::try_new_with_standby(phase_pin, enable_pin, standby_pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_with_standby`, but returns an error instead of panicking if a pin is not available
+    pub fn try_new_with_standby(
+        phase_pin: u8,
+        enable_pin: u8,
+        standby_pin: u8,
+    ) -> Result<PhaseEnableMotor> {
+        let phase = DigitalOutputDevice::try_new(phase_pin)?;
+        let enable = PWMOutputDevice::try_new(enable_pin)?;
+        Ok(PhaseEnableMotor(Motor::with_driver(
+            MotorDriver::PhaseEnable(phase, enable),
+            Some(MotorEnable::try_new(standby_pin)?),
+        )))
+    }
+
+    /// Drive the motor forwards at the current speed.
+    /// You can change the speed using `set_speed` before calling `forward`
+    pub fn forward(&mut self) -> Result<()> {
+        self.0.forward()
+    }
+
+    /// Drive the motor backwards.
+    /// You can change the speed using `set_speed` before calling `backward`
+    pub fn backward(&mut self) -> Result<()> {
+        self.0.backward()
+    }
+
+    /// Reverse the current direction of the motor, keeping its speed.
+    /// If the motor is stopped it remains stopped.
+    pub fn reverse(&mut self) -> Result<()> {
+        self.0.reverse()
+    }
+
+    /// Stop the motor immediately, even if an acceleration is set.
+    pub fn stop(&mut self) -> Result<()> {
+        self.0.stop()
+    }
+
+    /// The current speed of the motor, between -1.0 (full speed backward)
+    /// and 1.0 (full speed forward).
+    pub fn value(&self) -> f64 {
+        self.0.value()
+    }
+
+    /// Drive the motor at the given speed, between -1.0 (full speed backward)
+    /// and 1.0 (full speed forward). The sign of the value selects the direction.
+    /// Returns `Error::OutOfRange` for any other value.
+    pub fn set_value(&mut self, value: f64) -> Result<()> {
+        self.0.set_value(value)
+    }
+
+    /// Returns ``True`` if the motor is currently running and ``False`` otherwise.
+    pub fn is_active(&self) -> bool {
+        self.0.is_active()
+    }
+
+    /// The maximum rate of change of the motor's speed, in speed units per second,
+    /// or `None` (the default) to change speed immediately.
+    pub fn acceleration(&self) -> Option<f64> {
+        self.0.acceleration()
+    }
+
+    /// Limit the rate of change of the motor's speed, so that starting, stopping and changing
+    /// direction doesn't draw a sudden current. See `Motor::set_acceleration`.
+    pub fn set_acceleration(&mut self, acceleration: Option<f64>) -> Result<()> {
+        self.0.set_acceleration(acceleration)
+    }
+
+    /// The speed at which the motor should turn.
+    /// Can be any value between 0.0 (stopped) and the default 1.0 (maximum speed).
+    /// Returns `Error::OutOfRange` for any other value.
+    pub fn set_speed(&mut self, speed: f64) -> Result<()> {
+        self.0.set_speed(speed)
+    }
+
+    /// The frequency of the PWM signal driving the motor in Hz. Defaults to 100 Hz.
+    pub fn frequency(&self) -> f64 {
+        self.0.frequency()
+    }
+
+    /// Set the frequency of the PWM signal driving the motor in Hz, keeping the current speed.
+    /// Motor drivers often need a frequency between 1 and 20 kHz.
+    /// Returns `Error::OutOfRange` if the frequency isn't positive.
+    pub fn set_frequency(&mut self, frequency: f64) -> Result<()> {
+        self.0.set_frequency(frequency)
+    }
+
+    /// Continuously feed the values of `source` (e.g. the `values()` of another device)
+    /// into this device from a background thread, until the source is exhausted or
+    /// `clear_source` is called. Replaces any previously set source.
+    pub fn set_source<I>(&mut self, source: I)
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+        I::Item: Into<f64>,
+    {
+        self.0.set_source(source)
+    }
+
+    /// Stop feeding values from the source set with `set_source`.
+    pub fn clear_source(&self) {
+        self.0.clear_source()
+    }
+
+    /// The delay in seconds between two values read from the source. Defaults to 0.01.
+    pub fn source_delay(&self) -> f32 {
+        self.0.source_delay()
+    }
+
+    /// Set the delay in seconds between two values read from the source.
+    /// Takes effect the next time `set_source` is called.
+    /// Returns `Error::OutOfRange` if the delay is negative or infinite.
+    pub fn set_source_delay(&mut self, seconds: f32) -> Result<()> {
+        self.0.set_source_delay(seconds)
+    }
+}
+
+impl From<PhaseEnableMotor> for Motor {
+    /// Converts the motor into a `Motor` with the same phase/enable driver, e.g. to build a `Robot`
+    fn from(motor: PhaseEnableMotor) -> Motor {
+        motor.0
+    }
+}
+
+/// Represents a PWM-controlled servo motor connected to a GPIO pin.
+//reference :https://github.com/golemparts/rppal/blob/master/examples/gpio_servo_softpwm.rs
+pub struct Servo {
+    pin: Arc<Mutex<Box<dyn OutputPin>>>,
+    min_pulse_width: u64,
+    max_pulse_width: u64,
+    frame_width: u64,
+    source: Mutex<Option<SourcePump>>,
+    source_delay: f32,
+}
+
+impl Servo {
+    /// Returns a Servo with the pin number given with default `min_pulse_width` of 1ms,
+    /// `max_pulse_width` of 2ms and `frame_width` of 20ms
+    ///
+    /// * `pin` - The GPIO pin which the device is attached to
+    ///  
+    pub fn new(pin: u8) -> Servo {
+        match Servo::try_new(pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<Servo> {
+        Servo::try_new_with_pwm_mode(pin, PwmMode::Auto)
+    }
+
+    /// Returns a Servo with the pin number given, generating its PWM signal as selected by `mode`.
+    /// Hardware PWM avoids the jitter of software PWM, which makes servos twitch under CPU load.
+    ///
+    /// * `pin` - The GPIO pin which the device is attached to
+    /// * `mode` - Whether to use hardware or software PWM
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_gpiozero::pins::{self, MockFactory};
+    /// use rust_gpiozero::*;
+    ///
+    /// let factory = MockFactory::new();
+    /// pins::set_default_factory(factory.clone());
+    ///
+    /// // BCM 18 is routed to a hardware PWM channel
+    /// let mut servo = Servo::new_with_pwm_mode(18, PwmMode::Hardware);
+    /// servo.max().unwrap();
+    /// assert!(factory.get(18).is_hardware_pwm());
+    ///
+    /// // BCM 17 isn't
+    /// assert!(Servo::try_new_with_pwm_mode(17, PwmMode::Hardware).is_err());
+    /// let _servo = Servo::new(17);
+    /// assert!(!factory.get(17).is_hardware_pwm());
+    /// ```
+    pub fn new_with_pwm_mode(pin: u8, mode: PwmMode) -> Servo {
+        match Servo::try_new_with_pwm_mode(pin, mode) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_with_pwm_mode`, but returns an error instead of panicking if the pin is not
+    /// available, or doesn't support the PWM mode requested
+    pub fn try_new_with_pwm_mode(pin: u8, mode: PwmMode) -> Result<Servo> {
+        Ok(Servo {
+            pin: Arc::new(Mutex::new(pwm_pin(pin, mode)?)),
+            min_pulse_width: 1000,
+            max_pulse_width: 2000,
+            frame_width: 20,
+            source: Mutex::new(None),
+            source_delay: DEFAULT_SOURCE_DELAY,
+        })
+    }
+
+    /// Sends pulses of `pulse_width` microseconds, replacing any source
+    fn set_pulse_width(&mut self, pulse_width: u64) -> Result<()> {
+        self.clear_source();
+        self.pin.lock().unwrap().set_pwm(
+            Duration::from_millis(self.frame_width),
+            Duration::from_micros(pulse_width),
+        )
+    }
+
+    /// Maps a position between -1.0 and 1.0 to a pulse width between `min` and `max` linearly
+    fn position_to_pulse_width(min: u64, max: u64, value: f64) -> Result<u64> {
+        if !(-1.0..=1.0).contains(&value) {
+            return Err(Error::OutOfRange {
+                value,
+                min: -1.0,
+                max: 1.0,
+            });
         }
+        let range: f64 = (max - min) as f64;
+        Ok(min + (((value + 1.0) / 2.0) * range).round() as u64)
+    }
+
+    /// Set the servo to its minimum position.
+    pub fn min(&mut self) -> Result<()> {
+        self.set_pulse_width(self.min_pulse_width)
+    }
+
+    /// Set the servo to its maximum position.
+    pub fn max(&mut self) -> Result<()> {
+        self.set_pulse_width(self.max_pulse_width)
+    }
+
+    /// Set the servo to its neutral position.
+    pub fn mid(&mut self) -> Result<()> {
+        let mid_value = (self.min_pulse_width + self.max_pulse_width) / 2;
+        self.set_pulse_width(mid_value)
+    }
+
+    /// Set servo to any position between min and max.
+    /// value must be between -1 (the minimun position) and +1 (the maximum position),
+    /// otherwise `Error::OutOfRange` is returned.
+    pub fn set_position(&mut self, value: f64) -> Result<()> {
+        let pulse_width =
+            Servo::position_to_pulse_width(self.min_pulse_width, self.max_pulse_width, value)?;
+        self.set_pulse_width(pulse_width)
     }
- 
-    /// Set the servo's minimum pulse width
-    pub fn set_min_pulse_width(&mut self, value: u64) {
+
+    /// Set the servo's minimum pulse width.
+    /// Returns `Error::InvalidPulseWidth` unless it is less than the maximum pulse width.
+    pub fn set_min_pulse_width(&mut self, value: u64) -> Result<()> {
         if value >= self.max_pulse_width {
-            println!("min_pulse_width must be less than max_pulse_width");
-        } else {
-            self.min_pulse_width = value;
+            return Err(Error::InvalidPulseWidth(value));
         }
+        self.min_pulse_width = value;
+        Ok(())
     }
 
-    /// Set the servo's maximum pulse width
-    pub fn set_max_pulse_width(&mut self, value: u64) {
+    /// Set the servo's maximum pulse width.
+    /// Returns `Error::InvalidPulseWidth` unless it is less than the frame width.
+    pub fn set_max_pulse_width(&mut self, value: u64) -> Result<()> {
         if value >= self.frame_width * 1000 {
-            println!("max_pulse_width must be less than frame_width");
-        } else {
-            self.max_pulse_width = value;
+            return Err(Error::InvalidPulseWidth(value));
         }
+        self.max_pulse_width = value;
+        Ok(())
     }
 
     /// Set the servo's frame width(The time between control pulses, measured in milliseconds.)
-    pub fn set_frame_width(&mut self, value: u64) {
+    /// Returns `Error::InvalidPulseWidth` with the maximum pulse width unless the frame is
+    /// longer than it.
+    pub fn set_frame_width(&mut self, value: u64) -> Result<()> {
+        if value.saturating_mul(1000) <= self.max_pulse_width {
+            return Err(Error::InvalidPulseWidth(self.max_pulse_width));
+        }
         self.frame_width = value;
+        Ok(())
     }
 
     /// Get the servo's minimum pulse width
@@ -795,13 +2383,336 @@ impl Servo {
         self.frame_width
     }
 
-    pub fn detach(&mut self) {
-         if self
-            .pin
-            .clear_pwm()
-            .is_err()
-        {
-            println!("Failed to detach servo")
+    /// Stop sending control pulses, which lets the servo move freely.
+    /// Also stops feeding values from the source set with `set_source`.
+    pub fn detach(&mut self) -> Result<()> {
+        self.clear_source();
+        self.pin.lock().unwrap().clear_pwm()
+    }
+
+    /// Returns the function writing source values to the servo, using the pulse widths
+    /// configured when the source is set. Values must be between -1.0 and 1.0.
+    fn source_writer(&mut self) -> impl FnMut(f64) -> Result<()> + Send + 'static {
+        let pin = Arc::clone(&self.pin);
+        let (min, max) = (self.min_pulse_width, self.max_pulse_width);
+        let frame_width = Duration::from_millis(self.frame_width);
+        move |value| {
+            let pulse_width = Servo::position_to_pulse_width(min, max, value)?;
+            pin.lock()
+                .unwrap()
+                .set_pwm(frame_width, Duration::from_micros(pulse_width))
         }
-   }
+    }
+
+    impl_source!();
+}
+
+/// Plays `tone` on the pin of a tonal buzzer with a 50% duty cycle, or silences it for `None`.
+fn write_tone(device: &Mutex<OutputDevice>, tone: Option<Tone>) -> Result<()> {
+    let mut device = device.lock().unwrap();
+    match tone {
+        Some(tone) => {
+            device.pin.set_pwm_frequency(tone.frequency(), 0.5)?;
+            device.pwm_frequency = tone.frequency();
+            device.pwm_value = 0.5;
+        }
+        None => {
+            device.pin.clear_pwm()?;
+            device.pwm_value = 0.0;
+        }
+    }
+    Ok(())
+}
+
+/// A melody played by a `TonalBuzzer` on the scheduler thread
+struct Melody {
+    device: Arc<Mutex<OutputDevice>>,
+    notes: Vec<(Option<Tone>, Duration)>,
+    next: usize,
+    deadline: Instant,
+}
+
+impl Task for Melody {
+    /// Plays the next note, or silences the buzzer once the melody is over.
+    /// Stops with the error if a note can't be played, trying to silence the buzzer.
+    fn step(&mut self) -> Result<Option<Instant>> {
+        match self.notes.get(self.next) {
+            Some(&(tone, duration)) => {
+                if let Err(err) = write_tone(&self.device, tone) {
+                    let _ = write_tone(&self.device, None);
+                    return Err(err);
+                }
+                self.next += 1;
+                self.deadline += duration;
+                Ok(Some(self.deadline))
+            }
+            None => {
+                let _ = write_tone(&self.device, None);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Represents a tonal buzzer: a piezo buzzer driven by PWM, which plays tones by changing
+/// the frequency of its signal.
+///
+/// The buzzer plays tones within `octaves` octaves of its mid tone (A4 by default).
+/// Its value ranges from -1.0 (the lowest tone) to 1.0 (the highest tone), 0.0 being the
+/// mid tone, so it can also be fed from a source.
+///
+/// # Example
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::tones::Tone;
+/// use rust_gpiozero::*;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut buzzer = TonalBuzzer::new(18);
+/// buzzer.play("C5".parse().unwrap()).unwrap();
+/// assert_eq!(buzzer.tone().and_then(|tone| tone.note()), Some("C5".to_string()));
+/// assert_eq!(factory.get(18).value(), 0.5);
+///
+/// // Beyond the default range of one octave around A4
+/// assert!(buzzer.play("A6".parse().unwrap()).is_err());
+///
+/// let melody = vec![("E5", 0.01), ("D5", 0.01), ("C5", 0.02)]
+///     .into_iter()
+///     .map(|(note, duration)| (note.parse::<Tone>().unwrap(), duration));
+/// buzzer.play_melody(melody).unwrap().wait();
+/// assert!(!buzzer.is_active());
+/// ```
+#[derive(Debug)]
+pub struct TonalBuzzer {
+    device: Arc<Mutex<OutputDevice>>,
+    mid_tone: Tone,
+    octaves: u8,
+    handle: Option<TaskHandle>,
+    source: Mutex<Option<SourcePump>>,
+    source_delay: f32,
+}
+
+impl TonalBuzzer {
+    /// Returns a TonalBuzzer with the pin number given, playing tones within one octave of A4
+    ///
+    /// * `pin` - The GPIO pin which the buzzer is attached to
+    pub fn new(pin: u8) -> TonalBuzzer {
+        match TonalBuzzer::try_new(pin) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking if the pin is not available
+    pub fn try_new(pin: u8) -> Result<TonalBuzzer> {
+        TonalBuzzer::try_new_with_range(pin, Tone::from_midi(69)?, 1)
+    }
+
+    /// Returns a TonalBuzzer with the pin number given, playing tones within `octaves`
+    /// octaves of `mid_tone`
+    ///
+    /// * `pin` - The GPIO pin which the buzzer is attached to
+    /// * `mid_tone` - The tone played for a value of 0.0
+    /// * `octaves` - The number of octaves above and below the mid tone that can be played
+    pub fn new_with_range(pin: u8, mid_tone: Tone, octaves: u8) -> TonalBuzzer {
+        match TonalBuzzer::try_new_with_range(pin, mid_tone, octaves) {
+            Err(e) => panic!("{:?}", e),
+            Ok(device) => device,
+        }
+    }
+
+    /// Like `new_with_range`, but returns an error instead of panicking if the pin is not
+    /// available, or `Error::OutOfRange` if `octaves` is 0
+    pub fn try_new_with_range(pin: u8, mid_tone: Tone, octaves: u8) -> Result<TonalBuzzer> {
+        if octaves == 0 {
+            return Err(Error::OutOfRange {
+                value: 0.0,
+                min: 1.0,
+                max: f64::from(u8::MAX),
+            });
+        }
+        Ok(TonalBuzzer {
+            device: Arc::new(Mutex::new(OutputDevice::with_pin(pwm_pin(
+                pin,
+                PwmMode::Auto,
+            )?))),
+            mid_tone,
+            octaves,
+            handle: None,
+            source: Mutex::new(None),
+            source_delay: DEFAULT_SOURCE_DELAY,
+        })
+    }
+
+    /// The `Pin` that the buzzer is connected to.
+    pub fn pin(&self) -> u8 {
+        self.device.lock().unwrap().pin.pin()
+    }
+
+    /// The tone played for a value of 0.0
+    pub fn mid_tone(&self) -> Tone {
+        self.mid_tone
+    }
+
+    /// The lowest tone that can be played, for a value of -1.0
+    pub fn min_tone(&self) -> Tone {
+        self.mid_tone.down(12 * i32::from(self.octaves))
+    }
+
+    /// The highest tone that can be played, for a value of 1.0
+    pub fn max_tone(&self) -> Tone {
+        self.mid_tone.up(12 * i32::from(self.octaves))
+    }
+
+    /// The number of octaves above and below the mid tone that can be played
+    pub fn octaves(&self) -> u8 {
+        self.octaves
+    }
+
+    /// Returns `Error::OutOfRange` if `tone` is outside the range of the buzzer
+    fn check(&self, tone: Tone) -> Result<()> {
+        // Allow for rounding errors in tones computed from other tones
+        let min = self.min_tone().frequency() * (1.0 - 1e-9);
+        let max = self.max_tone().frequency() * (1.0 + 1e-9);
+        if !(min..=max).contains(&tone.frequency()) {
+            return Err(Error::OutOfRange {
+                value: tone.frequency(),
+                min: self.min_tone().frequency(),
+                max: self.max_tone().frequency(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Stops any melody in progress, and the source set with `set_source`
+    fn cancel(&mut self) {
+        self.clear_source();
+        if let Some(handle) = &self.handle {
+            handle.cancel();
+        }
+    }
+
+    /// Play `tone` until another tone is played or the buzzer is stopped, replacing any
+    /// melody or source. Returns `Error::OutOfRange` if the tone is outside the range of
+    /// the buzzer.
+    pub fn play(&mut self, tone: Tone) -> Result<()> {
+        self.check(tone)?;
+        self.cancel();
+        write_tone(&self.device, Some(tone))
+    }
+
+    /// Stop playing any tone or melody, and stop feeding values from the source set with
+    /// `set_source`.
+    pub fn stop(&mut self) -> Result<()> {
+        self.cancel();
+        write_tone(&self.device, None)
+    }
+
+    /// The tone currently played, or `None` if the buzzer is silent
+    pub fn tone(&self) -> Option<Tone> {
+        let device = self.device.lock().unwrap();
+        if device.pwm_value == 0.0 {
+            return None;
+        }
+        Tone::from_frequency(device.pwm_frequency).ok()
+    }
+
+    /// Returns ``True`` if the buzzer is currently playing a tone and ``False`` otherwise.
+    pub fn is_active(&self) -> bool {
+        self.tone().is_some()
+    }
+
+    /// The tone currently played, between -1.0 (the lowest tone) and 1.0 (the highest tone),
+    /// or `None` if the buzzer is silent
+    pub fn value(&self) -> Option<f64> {
+        self.tone().map(|tone| {
+            (tone.frequency() / self.mid_tone.frequency()).log2() / f64::from(self.octaves)
+        })
+    }
+
+    /// Play the tone corresponding to `value`, between -1.0 (the lowest tone) and 1.0 (the
+    /// highest tone), replacing any melody or source. Returns `Error::OutOfRange` for other
+    /// values.
+    pub fn set_value(&mut self, value: f64) -> Result<()> {
+        let tone = value_to_tone(self.mid_tone, self.octaves, value)?;
+        self.cancel();
+        write_tone(&self.device, Some(tone))
+    }
+
+    /// Play a melody in the background, replacing any tone, melody or source in progress.
+    /// Each note is a tone (or `None` for a rest) and its duration in seconds.
+    /// The buzzer is silent once the melody is over.
+    /// Returns a handle to wait for the melody to finish, or `Error::OutOfRange` without
+    /// playing anything if a tone is outside the range of the buzzer, or a duration is
+    /// negative or infinite.
+    pub fn play_melody<I, T>(&mut self, melody: I) -> Result<BlinkHandle>
+    where
+        I: IntoIterator<Item = (T, f32)>,
+        T: Into<Option<Tone>>,
+    {
+        let notes = melody
+            .into_iter()
+            .map(|(tone, duration)| {
+                let tone = tone.into();
+                if let Some(tone) = tone {
+                    self.check(tone)?;
+                }
+                if !(0.0..=scheduler::MAX_TIME.as_secs_f32()).contains(&duration) {
+                    return Err(Error::OutOfRange {
+                        value: f64::from(duration),
+                        min: 0.0,
+                        max: scheduler::MAX_TIME.as_secs_f64(),
+                    });
+                }
+                Ok((tone, Duration::from_secs_f32(duration)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.cancel();
+        let handle = scheduler::schedule(Melody {
+            device: Arc::clone(&self.device),
+            notes,
+            next: 0,
+            deadline: Instant::now(),
+        });
+        let completion = handle.completion();
+        self.handle = Some(handle);
+        Ok(completion)
+    }
+
+    /// Stops any melody and returns the function writing source values to the buzzer.
+    fn source_writer(&mut self) -> impl FnMut(f64) -> Result<()> + Send + 'static {
+        self.cancel();
+        let device = Arc::clone(&self.device);
+        let (mid_tone, octaves) = (self.mid_tone, self.octaves);
+        move |value| write_tone(&device, Some(value_to_tone(mid_tone, octaves, value)?))
+    }
+
+    impl_source!();
+
+    /// Shut down the buzzer and release all associated resources.
+    pub fn close(self) {
+        drop(self)
+    }
+}
+
+impl Drop for TonalBuzzer {
+    /// Stops any melody or source and silences the buzzer.
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Maps a value between -1.0 and 1.0 to a tone within `octaves` octaves of `mid_tone`
+fn value_to_tone(mid_tone: Tone, octaves: u8, value: f64) -> Result<Tone> {
+    if !(-1.0..=1.0).contains(&value) {
+        return Err(Error::OutOfRange {
+            value,
+            min: -1.0,
+            max: 1.0,
+        });
+    }
+    Tone::from_frequency(mid_tone.frequency() * 2f64.powf(value * f64::from(octaves)))
 }
@@ -0,0 +1,533 @@
+//! Pin backend for the Linux GPIO character device (`/dev/gpiochipN`), built on the v2 uAPI
+//!
+//! Unlike rppal, this backend isn't limited to the Raspberry Pi: it works on any board whose
+//! kernel exposes its GPIO controller as a gpiochip, including the `gpio-sim` and
+//! `gpio-mockup` kernel modules. Pin numbers are line offsets on the selected chip.
+use super::{InputPin, Level, OutputPin, Pin, PinFactory, PullUpDown, Trigger};
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const CONSUMER: &[u8] = b"rust_gpiozero";
+
+const GPIO_MAX_NAME_SIZE: usize = 32;
+const GPIO_V2_LINES_MAX: usize = 64;
+const GPIO_V2_LINE_NUM_ATTRS_MAX: usize = 10;
+
+const GPIO_V2_LINE_FLAG_INPUT: u64 = 1 << 2;
+const GPIO_V2_LINE_FLAG_OUTPUT: u64 = 1 << 3;
+const GPIO_V2_LINE_FLAG_EDGE_RISING: u64 = 1 << 4;
+const GPIO_V2_LINE_FLAG_EDGE_FALLING: u64 = 1 << 5;
+const GPIO_V2_LINE_FLAG_OPEN_DRAIN: u64 = 1 << 6;
+const GPIO_V2_LINE_FLAG_OPEN_SOURCE: u64 = 1 << 7;
+const GPIO_V2_LINE_FLAG_BIAS_PULL_UP: u64 = 1 << 8;
+const GPIO_V2_LINE_FLAG_BIAS_PULL_DOWN: u64 = 1 << 9;
+const GPIO_V2_LINE_FLAG_BIAS_DISABLED: u64 = 1 << 10;
+
+const GPIO_V2_LINE_ATTR_ID_DEBOUNCE: u32 = 3;
+
+const GPIO_V2_LINE_EVENT_RISING_EDGE: u32 = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+union LineAttributeValue {
+    flags: u64,
+    values: u64,
+    debounce_period_us: u32,
+}
+
+#[repr(C, align(8))]
+#[derive(Clone, Copy)]
+struct LineAttribute {
+    id: u32,
+    padding: u32,
+    value: LineAttributeValue,
+}
+
+#[repr(C, align(8))]
+#[derive(Clone, Copy)]
+struct LineConfigAttribute {
+    attr: LineAttribute,
+    mask: u64,
+}
+
+#[repr(C, align(8))]
+#[derive(Clone, Copy)]
+struct LineConfig {
+    flags: u64,
+    num_attrs: u32,
+    padding: [u32; 5],
+    attrs: [LineConfigAttribute; GPIO_V2_LINE_NUM_ATTRS_MAX],
+}
+
+#[repr(C, align(8))]
+struct LineRequest {
+    offsets: [u32; GPIO_V2_LINES_MAX],
+    consumer: [u8; GPIO_MAX_NAME_SIZE],
+    config: LineConfig,
+    num_lines: u32,
+    event_buffer_size: u32,
+    padding: [u32; 5],
+    fd: i32,
+}
+
+#[repr(C)]
+struct ChipInfo {
+    name: [u8; GPIO_MAX_NAME_SIZE],
+    label: [u8; GPIO_MAX_NAME_SIZE],
+    lines: u32,
+}
+
+#[repr(C, align(8))]
+struct LineValues {
+    bits: u64,
+    mask: u64,
+}
+
+#[repr(C, align(8))]
+struct LineEvent {
+    timestamp_ns: u64,
+    id: u32,
+    offset: u32,
+    seqno: u32,
+    line_seqno: u32,
+    padding: [u32; 6],
+}
+
+// The layouts above must match <linux/gpio.h> exactly
+const _: () = assert!(mem::size_of::<LineConfig>() == 272);
+const _: () = assert!(mem::size_of::<LineRequest>() == 592);
+const _: () = assert!(mem::size_of::<LineEvent>() == 48);
+const _: () = assert!(mem::size_of::<ChipInfo>() == 68);
+
+const fn iowr<T>(nr: u64) -> u64 {
+    (3 << 30) | ((mem::size_of::<T>() as u64) << 16) | (0xB4 << 8) | nr
+}
+
+const fn ior<T>(nr: u64) -> u64 {
+    (2 << 30) | ((mem::size_of::<T>() as u64) << 16) | (0xB4 << 8) | nr
+}
+
+const GPIO_GET_CHIPINFO_IOCTL: u64 = ior::<ChipInfo>(0x01);
+const GPIO_V2_GET_LINE_IOCTL: u64 = iowr::<LineRequest>(0x07);
+const GPIO_V2_LINE_SET_CONFIG_IOCTL: u64 = iowr::<LineConfig>(0x0D);
+const GPIO_V2_LINE_GET_VALUES_IOCTL: u64 = iowr::<LineValues>(0x0E);
+const GPIO_V2_LINE_SET_VALUES_IOCTL: u64 = iowr::<LineValues>(0x0F);
+
+fn ioctl<T>(fd: RawFd, request: u64, arg: &mut T) -> io::Result<()> {
+    // Safety: every request is paired with the struct its size was computed from
+    if unsafe { libc::ioctl(fd, request as _, arg as *mut T) } < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Output drive mode of a gpiochip line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drive {
+    /// Drive the line both high and low (default).
+    PushPull,
+    /// Only drive the line low, and let an external pull-up resistor pull it high.
+    OpenDrain,
+    /// Only drive the line high, and let an external pull-down resistor pull it low.
+    OpenSource,
+}
+
+/// Creates pins on a Linux GPIO character device using the v2 uAPI.
+///
+/// Requires the `gpiochip` feature. Line bias follows the pull-up/pull-down setting of each
+/// device. Drive mode and kernel-side debouncing can be configured per line before the
+/// factory is installed.
+///
+/// # Example
+///
+/// ```no_run
+/// use rust_gpiozero::pins::{self, Drive, GpiochipFactory};
+/// use rust_gpiozero::*;
+/// use std::time::Duration;
+///
+/// let mut factory = GpiochipFactory::new("/dev/gpiochip0");
+/// // Let the kernel debounce the button on line 17
+/// factory.set_debounce(17, Duration::from_millis(10)).unwrap();
+/// factory.set_drive(27, Drive::OpenDrain);
+/// pins::set_default_factory(factory);
+///
+/// let mut button = Button::new(17);
+/// let mut led = LED::new(27);
+/// button.wait_for_press(None);
+/// led.on();
+/// ```
+///
+/// # Panics
+///
+/// Reading or writing the level of a pin panics if the kernel rejects it (e.g. because the
+/// chip was removed), as [`Pin::read`], [`OutputPin::set_high`] and [`OutputPin::set_low`]
+/// can't return an error.
+#[derive(Debug, Clone)]
+pub struct GpiochipFactory {
+    chip: PathBuf,
+    drive: HashMap<u8, Drive>,
+    debounce: HashMap<u8, Duration>,
+}
+
+impl GpiochipFactory {
+    /// Returns a GpiochipFactory for the character device at the given path, e.g. `/dev/gpiochip0`
+    pub fn new<P: AsRef<Path>>(chip: P) -> GpiochipFactory {
+        GpiochipFactory {
+            chip: chip.as_ref().to_path_buf(),
+            drive: HashMap::new(),
+            debounce: HashMap::new(),
+        }
+    }
+
+    /// Set the drive mode used when the given line is configured as an output
+    pub fn set_drive(&mut self, pin: u8, drive: Drive) {
+        self.drive.insert(pin, drive);
+    }
+
+    /// Set the debounce period applied by the kernel when the given line is configured as an input.
+    /// Returns `Error::OutOfRange` if the period in microseconds doesn't fit in 32 bits
+    /// (about 71 minutes), the limit of the kernel.
+    pub fn set_debounce(&mut self, pin: u8, period: Duration) -> Result<()> {
+        if period.as_micros() > u128::from(u32::MAX) {
+            return Err(Error::OutOfRange {
+                value: period.as_secs_f64(),
+                min: 0.0,
+                max: f64::from(u32::MAX) / 1e6,
+            });
+        }
+        self.debounce.insert(pin, period);
+        Ok(())
+    }
+
+    fn request(&self, pin: u8, flags: u64) -> Result<GpiochipPin> {
+        let chip = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.chip)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::PermissionDenied => {
+                    Error::PermissionDenied(self.chip.display().to_string())
+                }
+                _ => Error::Io(err),
+            })?;
+
+        // The kernel also rejects unsupported flags with EINVAL, so check the offset first
+        // Safety: all fields are plain integers, for which zero is a valid value
+        let mut info: ChipInfo = unsafe { mem::zeroed() };
+        ioctl(chip.as_raw_fd(), GPIO_GET_CHIPINFO_IOCTL, &mut info).map_err(|err| {
+            match err.raw_os_error() {
+                Some(libc::ENOTTY) => Error::UnsupportedBoard,
+                _ => Error::Io(err),
+            }
+        })?;
+        if u32::from(pin) >= info.lines {
+            return Err(Error::UnknownPin(pin));
+        }
+
+        // Safety: all fields are plain integers, for which zero is a valid value
+        let mut request: LineRequest = unsafe { mem::zeroed() };
+        request.offsets[0] = u32::from(pin);
+        request.consumer[..CONSUMER.len()].copy_from_slice(CONSUMER);
+        request.num_lines = 1;
+        request.config.flags = flags;
+        if flags & GPIO_V2_LINE_FLAG_INPUT != 0 {
+            if let Some(period) = self.debounce.get(&pin) {
+                request.config.attrs[0] = LineConfigAttribute {
+                    attr: LineAttribute {
+                        id: GPIO_V2_LINE_ATTR_ID_DEBOUNCE,
+                        padding: 0,
+                        value: LineAttributeValue {
+                            // Checked by set_debounce
+                            debounce_period_us: period.as_micros() as u32,
+                        },
+                    },
+                    mask: 1,
+                };
+                request.config.num_attrs = 1;
+            }
+        }
+
+        ioctl(chip.as_raw_fd(), GPIO_V2_GET_LINE_IOCTL, &mut request).map_err(|err| {
+            match err.raw_os_error() {
+                Some(libc::EBUSY) => Error::PinBusy(pin),
+                Some(libc::EINVAL) => {
+                    Error::Unsupported("the requested bias, drive or debounce setting")
+                }
+                Some(libc::ENOTTY) => Error::UnsupportedBoard,
+                _ => Error::Io(err),
+            }
+        })?;
+
+        Ok(GpiochipPin {
+            pin,
+            // Safety: the kernel returned a new file descriptor we now own
+            line: unsafe { File::from_raw_fd(request.fd) },
+            config: request.config,
+            async_interrupt: None,
+        })
+    }
+}
+
+impl PinFactory for GpiochipFactory {
+    fn pin(&self, pin: u8) -> Result<Box<dyn Pin>> {
+        // Without a direction flag, the line keeps its current direction
+        Ok(Box::new(self.request(pin, 0)?))
+    }
+
+    fn input(&self, pin: u8, pull: PullUpDown) -> Result<Box<dyn InputPin>> {
+        let bias = match pull {
+            PullUpDown::Off => GPIO_V2_LINE_FLAG_BIAS_DISABLED,
+            PullUpDown::PullDown => GPIO_V2_LINE_FLAG_BIAS_PULL_DOWN,
+            PullUpDown::PullUp => GPIO_V2_LINE_FLAG_BIAS_PULL_UP,
+        };
+        Ok(Box::new(self.request(pin, GPIO_V2_LINE_FLAG_INPUT | bias)?))
+    }
+
+    fn output(&self, pin: u8) -> Result<Box<dyn OutputPin>> {
+        let drive = match self.drive.get(&pin) {
+            None | Some(Drive::PushPull) => 0,
+            Some(Drive::OpenDrain) => GPIO_V2_LINE_FLAG_OPEN_DRAIN,
+            Some(Drive::OpenSource) => GPIO_V2_LINE_FLAG_OPEN_SOURCE,
+        };
+        Ok(Box::new(
+            self.request(pin, GPIO_V2_LINE_FLAG_OUTPUT | drive)?,
+        ))
+    }
+}
+
+struct AsyncInterrupt {
+    stop: File,
+    handle: JoinHandle<()>,
+}
+
+/// A single line requested from a gpiochip
+struct GpiochipPin {
+    pin: u8,
+    line: File,
+    config: LineConfig,
+    async_interrupt: Option<AsyncInterrupt>,
+}
+
+impl fmt::Debug for GpiochipPin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GpiochipPin")
+            .field("pin", &self.pin)
+            .field("flags", &self.config.flags)
+            .finish()
+    }
+}
+
+/// Waits until the line has an edge event, or the timeout is reached.
+fn poll_line(line: RawFd, timeout: Option<Duration>) -> io::Result<bool> {
+    let timeout = timeout.map_or(-1, |timeout| {
+        timeout.as_millis().min(i32::MAX as u128) as i32
+    });
+    let mut fds = libc::pollfd {
+        fd: line,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // Safety: fds points to a single valid pollfd
+    match unsafe { libc::poll(&mut fds, 1, timeout) } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(false),
+        _ => Ok(true),
+    }
+}
+
+/// Reads the next edge event from the line and returns the level it changed to.
+fn read_event(line: RawFd) -> io::Result<Level> {
+    // Safety: LineEvent only consists of integers
+    let mut event: LineEvent = unsafe { mem::zeroed() };
+    let size = mem::size_of::<LineEvent>();
+    // Safety: the buffer is exactly one LineEvent long
+    let read = unsafe { libc::read(line, &mut event as *mut LineEvent as *mut _, size) };
+    if read < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if read as usize != size {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+    Ok(if event.id == GPIO_V2_LINE_EVENT_RISING_EDGE {
+        Level::High
+    } else {
+        Level::Low
+    })
+}
+
+impl GpiochipPin {
+    fn values(&self, request: u64, bits: u64) -> io::Result<u64> {
+        let mut values = LineValues { bits, mask: 1 };
+        ioctl(self.line.as_raw_fd(), request, &mut values)?;
+        Ok(values.bits)
+    }
+
+    fn set_edges(&mut self, trigger: Trigger) -> Result<()> {
+        let edges = match trigger {
+            Trigger::Disabled => 0,
+            Trigger::RisingEdge => GPIO_V2_LINE_FLAG_EDGE_RISING,
+            Trigger::FallingEdge => GPIO_V2_LINE_FLAG_EDGE_FALLING,
+            Trigger::Both => GPIO_V2_LINE_FLAG_EDGE_RISING | GPIO_V2_LINE_FLAG_EDGE_FALLING,
+        };
+        let mut config = self.config;
+        config.flags = config.flags
+            & !(GPIO_V2_LINE_FLAG_EDGE_RISING | GPIO_V2_LINE_FLAG_EDGE_FALLING)
+            | edges;
+        ioctl(
+            self.line.as_raw_fd(),
+            GPIO_V2_LINE_SET_CONFIG_IOCTL,
+            &mut config,
+        )?;
+        self.config = config;
+        Ok(())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the kernel rejects the new value of the line.
+    fn write(&mut self, level: Level) {
+        self.values(GPIO_V2_LINE_SET_VALUES_IOCTL, level as u64)
+            .expect("Could not write GPIO line value");
+    }
+}
+
+impl Drop for GpiochipPin {
+    fn drop(&mut self) {
+        let _ = self.clear_async_interrupt();
+    }
+}
+
+impl Pin for GpiochipPin {
+    fn pin(&self) -> u8 {
+        self.pin
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the kernel rejects reading the value of the line.
+    fn read(&self) -> Level {
+        match self.values(GPIO_V2_LINE_GET_VALUES_IOCTL, 0) {
+            Ok(bits) if bits & 1 == 1 => Level::High,
+            Ok(_) => Level::Low,
+            Err(e) => panic!("Could not read GPIO line value: {}", e),
+        }
+    }
+}
+
+impl InputPin for GpiochipPin {
+    fn set_interrupt(&mut self, trigger: Trigger) -> Result<()> {
+        self.clear_async_interrupt()?;
+        self.set_edges(trigger)
+    }
+
+    fn poll_interrupt(&mut self, reset: bool, timeout: Option<Duration>) -> Result<Option<Level>> {
+        let line = self.line.as_raw_fd();
+        if reset {
+            while poll_line(line, Some(Duration::from_secs(0)))? {
+                read_event(line)?;
+            }
+        }
+        if poll_line(line, timeout)? {
+            Ok(Some(read_event(line)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_async_interrupt(
+        &mut self,
+        trigger: Trigger,
+        mut callback: Box<dyn FnMut(Level) + Send>,
+    ) -> Result<()> {
+        self.clear_async_interrupt()?;
+        self.set_edges(trigger)?;
+
+        let mut fds = [0; 2];
+        // Safety: fds has room for both ends of the pipe
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        // Safety: pipe2 returned two new file descriptors we now own
+        let (wake, stop) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+        let line = self.line.as_raw_fd();
+
+        let handle = thread::spawn(move || {
+            let mut fds = [
+                libc::pollfd {
+                    fd: line,
+                    events: libc::POLLIN,
+                    revents: 0,
+                },
+                libc::pollfd {
+                    fd: wake.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                },
+            ];
+            loop {
+                // Safety: fds points to two valid pollfds
+                if unsafe { libc::poll(fds.as_mut_ptr(), 2, -1) } < 0 {
+                    if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    break;
+                }
+                if fds[1].revents != 0 {
+                    break;
+                }
+                if fds[0].revents & libc::POLLIN != 0 {
+                    match read_event(line) {
+                        Ok(level) => callback(level),
+                        Err(_) => break,
+                    }
+                }
+            }
+        });
+
+        self.async_interrupt = Some(AsyncInterrupt { stop, handle });
+        Ok(())
+    }
+
+    fn clear_async_interrupt(&mut self) -> Result<()> {
+        if let Some(mut interrupt) = self.async_interrupt.take() {
+            interrupt.stop.write_all(&[1])?;
+            if interrupt.handle.join().is_err() {
+                return Err(io::Error::other("interrupt thread panicked").into());
+            }
+            self.set_edges(Trigger::Disabled)?;
+        }
+        Ok(())
+    }
+}
+
+impl OutputPin for GpiochipPin {
+    fn set_high(&mut self) {
+        self.write(Level::High)
+    }
+
+    fn set_low(&mut self) {
+        self.write(Level::Low)
+    }
+
+    fn set_pwm(&mut self, _period: Duration, _pulse_width: Duration) -> Result<()> {
+        Err(Error::Unsupported("PWM"))
+    }
+
+    fn set_pwm_frequency(&mut self, _frequency: f64, _duty_cycle: f64) -> Result<()> {
+        Err(Error::Unsupported("PWM"))
+    }
+
+    fn clear_pwm(&mut self) -> Result<()> {
+        // PWM can never be active on a gpiochip line
+        Ok(())
+    }
+}
@@ -0,0 +1,495 @@
+//! Mock pin backend for testing device logic without a Raspberry Pi
+use super::rpi::{pwm_channel, PwmChannelClaim, PwmChannels};
+use super::{InputPin, Level, OutputPin, Pin, PinFactory, PullUpDown, Trigger};
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Creates [`MockPin`]s that record every state they are set to.
+///
+/// Cloning a `MockFactory` returns a handle to the same set of pins, so a test can install
+/// one clone as the default factory and keep another to drive inputs and inspect outputs.
+///
+/// # Example : Testing a Button callback
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::sync::Arc;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut button = Button::new_with_pulldown(17);
+/// let pressed = Arc::new(AtomicBool::new(false));
+/// let flag = pressed.clone();
+/// button
+///     .when_pressed(move |_| flag.store(true, Ordering::SeqCst))
+///     .unwrap();
+///
+/// // Callbacks run on the thread driving the pin
+/// factory.get(17).drive_high();
+/// assert!(pressed.load(Ordering::SeqCst));
+/// assert!(button.is_active());
+/// ```
+///
+/// # Example : Testing a blinking LED
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut led = LED::new(17);
+/// led.set_blink_count(2);
+/// led.blink(0.01, 0.01);
+/// led.wait();
+///
+/// assert_eq!(factory.get(17).states(), vec![0.0, 1.0, 0.0, 1.0, 0.0]);
+/// ```
+///
+/// # Example : Testing PWM devices
+///
+/// ```
+/// use rust_gpiozero::pins::{self, MockFactory};
+/// use rust_gpiozero::*;
+/// use std::time::Duration;
+///
+/// let factory = MockFactory::new();
+/// pins::set_default_factory(factory.clone());
+///
+/// let mut led = PWMLED::new(17);
+/// led.set_value(0.25).unwrap();
+/// assert_eq!(factory.get(17).value(), 0.25);
+/// assert_eq!(factory.get(17).frequency(), Some(100.0));
+///
+/// let mut servo = Servo::new(18);
+/// servo.mid().unwrap();
+/// assert_eq!(factory.get(18).pulse_width(), Some(Duration::from_micros(1500)));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct MockFactory {
+    pins: Arc<Mutex<HashMap<u8, MockPin>>>,
+    channels: PwmChannels,
+}
+
+impl MockFactory {
+    /// Returns a new MockFactory without any pins
+    pub fn new() -> MockFactory {
+        MockFactory::default()
+    }
+
+    /// Returns the mock pin with the given number, creating it in the low state if needed.
+    /// Devices created from this factory share the pin with the returned handle, but only
+    /// one device can use a pin at a time.
+    pub fn get(&self, pin: u8) -> MockPin {
+        self.pins
+            .lock()
+            .unwrap()
+            .entry(pin)
+            .or_insert_with(|| MockPin::new(pin))
+            .clone()
+    }
+
+    /// Forgets all pins, so that the next device created starts from a fresh pin.
+    pub fn reset(&self) {
+        self.pins.lock().unwrap().clear();
+    }
+
+    /// Returns the pin for use by a device, which holds it until the device is dropped.
+    fn claim(&self, pin: u8) -> Result<MockPin> {
+        let mut pin = self.get(pin);
+        if pin.claimed.swap(true, Ordering::SeqCst) {
+            return Err(Error::PinBusy(pin.pin));
+        }
+        pin.owner = true;
+        Ok(pin)
+    }
+}
+
+impl PinFactory for MockFactory {
+    fn pin(&self, pin: u8) -> Result<Box<dyn Pin>> {
+        Ok(Box::new(self.claim(pin)?))
+    }
+
+    fn input(&self, pin: u8, pull: PullUpDown) -> Result<Box<dyn InputPin>> {
+        let pin = self.claim(pin)?;
+        match pull {
+            PullUpDown::Off => {}
+            PullUpDown::PullDown => pin.drive_low(),
+            PullUpDown::PullUp => pin.drive_high(),
+        }
+        Ok(Box::new(pin))
+    }
+
+    fn output(&self, pin: u8) -> Result<Box<dyn OutputPin>> {
+        let pin = self.claim(pin)?;
+        pin.state.0.lock().unwrap().hardware_pwm = false;
+        Ok(Box::new(pin))
+    }
+
+    /// Simulates the hardware PWM channels of the Raspberry Pi on BCM pins 12, 13, 18 and 19.
+    /// Like on the Raspberry Pi, pins 12 and 18 share a channel, as do pins 13 and 19, so
+    /// this returns `Error::PinBusy` with the other pin of the channel if it is in use.
+    fn hardware_pwm(&self, pin: u8) -> Result<Box<dyn OutputPin>> {
+        let channel = pwm_channel(pin).ok_or(Error::Unsupported("hardware PWM"))?;
+        let mut pin = self.claim(pin)?;
+        pin.pwm_claim = Some(self.channels.claim(pin.pin, channel)?);
+        pin.state.0.lock().unwrap().hardware_pwm = true;
+        Ok(Box::new(pin))
+    }
+}
+
+/// A state recorded by a [`MockPin`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MockState {
+    /// Time elapsed between the creation of the pin and the change of state.
+    pub time: Duration,
+    /// `0.0` for low, `1.0` for high, or the duty cycle while PWM is active.
+    pub value: f64,
+    /// The PWM frequency in Hz, or `None` when the pin is not driven by PWM.
+    pub frequency: Option<f64>,
+}
+
+/// The async interrupt callback of a pin. It has its own lock, so that the callback can
+/// clear or replace the interrupt of its pin while it runs.
+type Callback = Arc<Mutex<Box<dyn FnMut(Level) + Send>>>;
+
+/// A simulated GPIO pin created by the [`MockFactory`].
+///
+/// Tests drive the level seen by input devices with `drive_high`/`drive_low` and inspect
+/// what output devices did through `value`, `frequency` and `history`.
+pub struct MockPin {
+    pin: u8,
+    created: Instant,
+    state: Arc<(Mutex<MockPinState>, Condvar)>,
+    callback: Arc<Mutex<Option<Callback>>>,
+    claimed: Arc<AtomicBool>,
+    /// Whether this handle is the one held by a device
+    owner: bool,
+    /// The hardware PWM channel held by the device driving this pin
+    pwm_claim: Option<PwmChannelClaim>,
+}
+
+struct MockPinState {
+    value: f64,
+    frequency: Option<f64>,
+    history: Vec<MockState>,
+    interrupt: Trigger,
+    pending: Option<Level>,
+    async_interrupt: Trigger,
+    hardware_pwm: bool,
+    pwm_fails: bool,
+}
+
+impl Clone for MockPin {
+    fn clone(&self) -> MockPin {
+        MockPin {
+            pin: self.pin,
+            created: self.created,
+            state: self.state.clone(),
+            callback: self.callback.clone(),
+            claimed: self.claimed.clone(),
+            owner: false,
+            pwm_claim: None,
+        }
+    }
+}
+
+impl Drop for MockPin {
+    fn drop(&mut self) {
+        if self.owner {
+            // Like a real pin, interrupts are cleared when the device releases the pin
+            let _ = self.set_interrupt(Trigger::Disabled);
+            let _ = self.clear_async_interrupt();
+            self.claimed.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+impl fmt::Debug for MockPin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockPin")
+            .field("pin", &self.pin)
+            .field("value", &self.value())
+            .field("frequency", &self.frequency())
+            .finish()
+    }
+}
+
+fn triggers(trigger: Trigger, level: Level) -> bool {
+    match trigger {
+        Trigger::Disabled => false,
+        Trigger::RisingEdge => level == Level::High,
+        Trigger::FallingEdge => level == Level::Low,
+        Trigger::Both => true,
+    }
+}
+
+impl MockPin {
+    fn new(pin: u8) -> MockPin {
+        MockPin {
+            pin,
+            created: Instant::now(),
+            state: Arc::new((
+                Mutex::new(MockPinState {
+                    value: 0.0,
+                    frequency: None,
+                    history: vec![MockState {
+                        time: Duration::from_secs(0),
+                        value: 0.0,
+                        frequency: None,
+                    }],
+                    interrupt: Trigger::Disabled,
+                    pending: None,
+                    async_interrupt: Trigger::Disabled,
+                    hardware_pwm: false,
+                    pwm_fails: false,
+                }),
+                Condvar::new(),
+            )),
+            callback: Arc::new(Mutex::new(None)),
+            claimed: Arc::new(AtomicBool::new(false)),
+            owner: false,
+            pwm_claim: None,
+        }
+    }
+
+    /// Records a new state and fires the interrupts triggered by the change of level.
+    fn set_state(&self, value: f64, frequency: Option<f64>) {
+        let (lock, changed) = &*self.state;
+        let mut state = lock.lock().unwrap();
+        if state.value == value && state.frequency == frequency {
+            return;
+        }
+        let old_level = level_of(state.value);
+        state.value = value;
+        state.frequency = frequency;
+        state.history.push(MockState {
+            time: self.created.elapsed(),
+            value,
+            frequency,
+        });
+        let new_level = level_of(value);
+        if old_level == new_level {
+            changed.notify_all();
+            return;
+        }
+        if triggers(state.interrupt, new_level) {
+            state.pending = Some(new_level);
+        }
+        let fire_async = triggers(state.async_interrupt, new_level);
+        changed.notify_all();
+        drop(state);
+
+        if fire_async {
+            let callback = self.callback.lock().unwrap().clone();
+            if let Some(callback) = callback {
+                (callback.lock().unwrap())(new_level);
+            }
+        }
+    }
+
+    /// Drives the pin to the given level, as an external circuit would.
+    /// Interrupts configured on the pin fire on the calling thread.
+    pub fn drive(&self, level: Level) {
+        match level {
+            Level::Low => self.set_state(0.0, None),
+            Level::High => self.set_state(1.0, None),
+        }
+    }
+
+    /// Drives the pin high, as an external circuit would.
+    pub fn drive_high(&self) {
+        self.drive(Level::High)
+    }
+
+    /// Drives the pin low, as an external circuit would.
+    pub fn drive_low(&self) {
+        self.drive(Level::Low)
+    }
+
+    /// Returns `0.0` when the pin is low, `1.0` when it is high, or the duty cycle while
+    /// PWM is active.
+    pub fn value(&self) -> f64 {
+        self.state.0.lock().unwrap().value
+    }
+
+    /// Returns the PWM frequency in Hz, or `None` when the pin is not driven by PWM.
+    pub fn frequency(&self) -> Option<f64> {
+        self.state.0.lock().unwrap().frequency
+    }
+
+    /// Returns the width of each PWM pulse, or `None` when the pin is not driven by PWM.
+    pub fn pulse_width(&self) -> Option<Duration> {
+        let state = self.state.0.lock().unwrap();
+        state
+            .frequency
+            .map(|frequency| Duration::from_nanos((state.value / frequency * 1e9).round() as u64))
+    }
+
+    /// Returns `true` if the pin was last claimed as a hardware PWM output.
+    pub fn is_hardware_pwm(&self) -> bool {
+        self.state.0.lock().unwrap().hardware_pwm
+    }
+
+    /// When `fail` is `true`, PWM operations on the pin return an I/O error, to test how
+    /// devices handle a failing pin.
+    pub fn set_pwm_fails(&self, fail: bool) {
+        self.state.0.lock().unwrap().pwm_fails = fail;
+    }
+
+    /// Returns an error if PWM operations were made to fail with `set_pwm_fails`
+    fn check_pwm(&self) -> Result<()> {
+        if self.state.0.lock().unwrap().pwm_fails {
+            return Err(Error::Io(io::Error::other("mock PWM failure")));
+        }
+        Ok(())
+    }
+
+    /// Returns every state the pin has been in, oldest first.
+    pub fn history(&self) -> Vec<MockState> {
+        self.state.0.lock().unwrap().history.clone()
+    }
+
+    /// Returns the value of every state the pin has been in, oldest first.
+    pub fn states(&self) -> Vec<f64> {
+        self.history().iter().map(|state| state.value).collect()
+    }
+
+    /// Forgets all recorded states except the current one.
+    pub fn clear_history(&self) {
+        let mut state = self.state.0.lock().unwrap();
+        let current = MockState {
+            time: self.created.elapsed(),
+            value: state.value,
+            frequency: state.frequency,
+        };
+        state.history = vec![current];
+    }
+
+    /// Blocks until the pin's value equals `value`, or the timeout is reached.
+    /// Returns `false` on timeout.
+    pub fn wait_for_value(&self, value: f64, timeout: Duration) -> bool {
+        let (lock, changed) = &*self.state;
+        let (state, _) = changed
+            .wait_timeout_while(lock.lock().unwrap(), timeout, |state| state.value != value)
+            .unwrap();
+        state.value == value
+    }
+}
+
+fn level_of(value: f64) -> Level {
+    if value > 0.0 {
+        Level::High
+    } else {
+        Level::Low
+    }
+}
+
+impl Pin for MockPin {
+    fn pin(&self) -> u8 {
+        self.pin
+    }
+
+    fn read(&self) -> Level {
+        level_of(self.value())
+    }
+}
+
+impl InputPin for MockPin {
+    fn set_interrupt(&mut self, trigger: Trigger) -> Result<()> {
+        let mut state = self.state.0.lock().unwrap();
+        state.interrupt = trigger;
+        state.pending = None;
+        Ok(())
+    }
+
+    fn poll_interrupt(&mut self, reset: bool, timeout: Option<Duration>) -> Result<Option<Level>> {
+        let (lock, changed) = &*self.state;
+        let mut state = lock.lock().unwrap();
+        if reset {
+            state.pending = None;
+        }
+        let mut state = match timeout {
+            Some(timeout) => {
+                changed
+                    .wait_timeout_while(state, timeout, |state| state.pending.is_none())
+                    .unwrap()
+                    .0
+            }
+            None => changed
+                .wait_while(state, |state| state.pending.is_none())
+                .unwrap(),
+        };
+        Ok(state.pending.take())
+    }
+
+    fn set_async_interrupt(
+        &mut self,
+        trigger: Trigger,
+        callback: Box<dyn FnMut(Level) + Send>,
+    ) -> Result<()> {
+        self.callback
+            .lock()
+            .unwrap()
+            .replace(Arc::new(Mutex::new(callback)));
+        self.state.0.lock().unwrap().async_interrupt = trigger;
+        Ok(())
+    }
+
+    fn clear_async_interrupt(&mut self) -> Result<()> {
+        self.state.0.lock().unwrap().async_interrupt = Trigger::Disabled;
+        self.callback.lock().unwrap().take();
+        Ok(())
+    }
+}
+
+impl OutputPin for MockPin {
+    fn set_high(&mut self) {
+        self.set_state(1.0, None);
+    }
+
+    fn set_low(&mut self) {
+        self.set_state(0.0, None);
+    }
+
+    fn set_pwm(&mut self, period: Duration, pulse_width: Duration) -> Result<()> {
+        self.check_pwm()?;
+        let period = period.as_secs_f64();
+        if period > 0.0 {
+            let duty_cycle = (pulse_width.as_secs_f64() / period).min(1.0);
+            self.set_state(duty_cycle, Some(1.0 / period));
+        } else {
+            self.set_state(0.0, Some(0.0));
+        }
+        Ok(())
+    }
+
+    fn set_pwm_frequency(&mut self, frequency: f64, duty_cycle: f64) -> Result<()> {
+        self.check_pwm()?;
+        self.set_state(duty_cycle.clamp(0.0, 1.0), Some(frequency));
+        Ok(())
+    }
+
+    fn clear_pwm(&mut self) -> Result<()> {
+        self.check_pwm()?;
+        let state = self.state.0.lock().unwrap();
+        if state.frequency.is_none() {
+            return Ok(());
+        }
+        // The PWM signal stops at the end of a cycle, which leaves the pin low
+        // unless it was permanently high
+        let value = if state.value >= 1.0 { 1.0 } else { 0.0 };
+        drop(state);
+        self.set_state(value, None);
+        Ok(())
+    }
+}
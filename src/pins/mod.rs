@@ -0,0 +1,152 @@
+//! Pin factories that decouple devices from the library used to access the GPIO pins
+//!
+//! Devices never talk to a GPIO library directly. Instead, they ask a [`PinFactory`] for
+//! a pin in the mode they need and drive it through the [`Pin`], [`InputPin`] and
+//! [`OutputPin`] traits. Unless another factory is installed with [`set_default_factory`],
+//! devices use the [`RppalFactory`], which accesses the Raspberry Pi GPIO peripheral
+//! through [rppal](https://github.com/golemparts/rppal). The [`MockFactory`] simulates pins
+//! in memory, which allows testing device logic on machines without GPIO hardware. With the
+//! `gpiochip` feature enabled, the `GpiochipFactory` drives pins through the Linux GPIO
+//! character device on any board.
+//!
+//! # Example : Using a custom backend
+//!
+//! ```no_run
+//! use rust_gpiozero::pins::{self, RppalFactory};
+//! use rust_gpiozero::*;
+//!
+//! // Any type implementing `PinFactory` can be installed here
+//! pins::set_default_factory(RppalFactory::new());
+//!
+//! // Devices created from now on get their pins from the installed factory
+//! let mut led = LED::new(17);
+//! led.on();
+//! ```
+use crate::error::{Error, Result};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "gpiochip")]
+mod gpiochip;
+mod mock;
+mod rpi;
+#[cfg(feature = "gpiochip")]
+pub use self::gpiochip::{Drive, GpiochipFactory};
+pub use self::mock::{MockFactory, MockPin, MockState};
+pub use self::rpi::RppalFactory;
+
+/// The logic level of a pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Level {
+    Low,
+    High,
+}
+
+/// The built-in pull-up/pull-down resistor setting of an input pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PullUpDown {
+    Off,
+    PullDown,
+    PullUp,
+}
+
+/// The edges of an input pin that trigger an interrupt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    Disabled,
+    RisingEdge,
+    FallingEdge,
+    Both,
+}
+
+/// Creates the pins used by devices.
+///
+/// Implement this trait to run devices against an alternative GPIO backend.
+pub trait PinFactory: Send + Sync {
+    /// Returns the pin with the given number without changing its mode.
+    fn pin(&self, pin: u8) -> Result<Box<dyn Pin>>;
+
+    /// Returns the pin with the given number configured as an input with the given
+    /// pull-up/pull-down resistor setting.
+    fn input(&self, pin: u8, pull: PullUpDown) -> Result<Box<dyn InputPin>>;
+
+    /// Returns the pin with the given number configured as an output.
+    fn output(&self, pin: u8) -> Result<Box<dyn OutputPin>>;
+
+    /// Returns the pin with the given number configured as an output driven by a hardware
+    /// PWM channel, which keeps a steady signal regardless of the CPU load.
+    /// The default implementation returns `Error::Unsupported` for every pin.
+    fn hardware_pwm(&self, pin: u8) -> Result<Box<dyn OutputPin>> {
+        let _ = pin;
+        Err(Error::Unsupported("hardware PWM"))
+    }
+}
+
+/// A GPIO pin whose logic level can be read.
+pub trait Pin: fmt::Debug + Send {
+    /// Returns the GPIO pin number.
+    fn pin(&self) -> u8;
+
+    /// Reads the pin's logic level.
+    fn read(&self) -> Level;
+}
+
+/// A GPIO pin configured as an input.
+pub trait InputPin: Pin {
+    /// Configures a synchronous interrupt trigger, to be polled with `poll_interrupt`.
+    fn set_interrupt(&mut self, trigger: Trigger) -> Result<()>;
+
+    /// Blocks until the interrupt configured with `set_interrupt` is triggered, or the
+    /// timeout is reached. Returns `None` on timeout.
+    fn poll_interrupt(&mut self, reset: bool, timeout: Option<Duration>) -> Result<Option<Level>>;
+
+    /// Configures an asynchronous interrupt trigger, which executes the callback on a
+    /// separate thread when the interrupt is triggered.
+    fn set_async_interrupt(
+        &mut self,
+        trigger: Trigger,
+        callback: Box<dyn FnMut(Level) + Send>,
+    ) -> Result<()>;
+
+    /// Removes a previously configured asynchronous interrupt trigger.
+    fn clear_async_interrupt(&mut self) -> Result<()>;
+}
+
+/// A GPIO pin configured as an output.
+pub trait OutputPin: Pin {
+    /// Sets the pin's output state to high.
+    fn set_high(&mut self);
+
+    /// Sets the pin's output state to low.
+    fn set_low(&mut self);
+
+    /// Configures PWM with the given period and pulse width.
+    fn set_pwm(&mut self, period: Duration, pulse_width: Duration) -> Result<()>;
+
+    /// Configures PWM with the given frequency (in Hz) and duty cycle (0.0 to 1.0).
+    fn set_pwm_frequency(&mut self, frequency: f64, duty_cycle: f64) -> Result<()>;
+
+    /// Stops PWM.
+    fn clear_pwm(&mut self) -> Result<()>;
+}
+
+static DEFAULT_FACTORY: Mutex<Option<Arc<dyn PinFactory>>> = Mutex::new(None);
+
+/// Returns the factory used by devices to create their pins.
+/// Defaults to [`RppalFactory`] unless another factory was installed with [`set_default_factory`].
+pub fn default_factory() -> Arc<dyn PinFactory> {
+    let mut factory = DEFAULT_FACTORY.lock().unwrap();
+    factory
+        .get_or_insert_with(|| Arc::new(RppalFactory::new()))
+        .clone()
+}
+
+/// Sets the factory used by devices created from now on.
+/// Devices that already exist keep the pins they were created with.
+pub fn set_default_factory<F>(factory: F)
+where
+    F: PinFactory + 'static,
+{
+    DEFAULT_FACTORY.lock().unwrap().replace(Arc::new(factory));
+}
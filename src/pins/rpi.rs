@@ -0,0 +1,303 @@
+//! Default pin backend for the Raspberry Pi, built on [rppal](https://github.com/golemparts/rppal)
+use super::{InputPin, Level, OutputPin, Pin, PinFactory, PullUpDown, Trigger};
+use crate::error::{Error, Result};
+use rppal::gpio::{self, Gpio, Mode};
+use rppal::pwm::{Channel, Polarity, Pwm};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// Number of GPIO pins exposed by the BCM283x/BCM2711 GPIO peripheral
+const PIN_COUNT: u8 = 54;
+
+/// Frequency in Hz used when a hardware PWM pin is set high or low before any frequency is set
+const DEFAULT_PWM_FREQUENCY: f64 = 100.0;
+
+/// Returns the hardware PWM channel that can be routed to the given BCM pin, if any.
+/// The channel must be enabled with the `pwm` or `pwm-2chan` overlay in `/boot/config.txt`.
+pub(crate) fn pwm_channel(pin: u8) -> Option<Channel> {
+    match pin {
+        12 | 18 => Some(Channel::Pwm0),
+        13 | 19 => Some(Channel::Pwm1),
+        _ => None,
+    }
+}
+
+/// Returns the alternate function routing its hardware PWM channel to the given BCM pin
+fn pwm_function(pin: u8) -> Mode {
+    match pin {
+        12 | 13 => Mode::Alt0,
+        _ => Mode::Alt5,
+    }
+}
+
+/// The pins driven by each hardware PWM channel. Both pins of a channel output the same
+/// signal, so only one device can use each channel at a time.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct PwmChannels(Arc<Mutex<[Option<u8>; 2]>>);
+
+impl PwmChannels {
+    /// Claims `channel` for `pin` until the returned claim is dropped. Returns
+    /// `Error::PinBusy` with the other pin of the channel if it is already in use.
+    pub(crate) fn claim(&self, pin: u8, channel: Channel) -> Result<PwmChannelClaim> {
+        let mut pins = self.0.lock().unwrap();
+        if let Some(other) = pins[channel as usize] {
+            return Err(Error::PinBusy(other));
+        }
+        pins[channel as usize] = Some(pin);
+        Ok(PwmChannelClaim {
+            channels: self.clone(),
+            channel,
+        })
+    }
+}
+
+/// Releases a hardware PWM channel when dropped
+#[derive(Debug)]
+pub(crate) struct PwmChannelClaim {
+    channels: PwmChannels,
+    channel: Channel,
+}
+
+impl Drop for PwmChannelClaim {
+    fn drop(&mut self) {
+        self.channels.0.lock().unwrap()[self.channel as usize] = None;
+    }
+}
+
+impl From<gpio::Level> for Level {
+    fn from(level: gpio::Level) -> Level {
+        match level {
+            gpio::Level::Low => Level::Low,
+            gpio::Level::High => Level::High,
+        }
+    }
+}
+
+impl From<Trigger> for gpio::Trigger {
+    fn from(trigger: Trigger) -> gpio::Trigger {
+        match trigger {
+            Trigger::Disabled => gpio::Trigger::Disabled,
+            Trigger::RisingEdge => gpio::Trigger::RisingEdge,
+            Trigger::FallingEdge => gpio::Trigger::FallingEdge,
+            Trigger::Both => gpio::Trigger::Both,
+        }
+    }
+}
+
+/// Creates pins on the Raspberry Pi GPIO peripheral using rppal.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RppalFactory;
+
+impl RppalFactory {
+    /// Returns a new RppalFactory
+    pub fn new() -> RppalFactory {
+        RppalFactory
+    }
+
+    fn get(&self, pin: u8) -> Result<gpio::Pin> {
+        if pin >= PIN_COUNT {
+            return Err(Error::UnknownPin(pin));
+        }
+        match Gpio::new()?.get(pin) {
+            // rppal reports both unknown and busy pins as unavailable
+            Err(gpio::Error::PinNotAvailable(pin)) => Err(Error::PinBusy(pin)),
+            result => Ok(result?),
+        }
+    }
+}
+
+impl PinFactory for RppalFactory {
+    fn pin(&self, pin: u8) -> Result<Box<dyn Pin>> {
+        Ok(Box::new(self.get(pin)?))
+    }
+
+    fn input(&self, pin: u8, pull: PullUpDown) -> Result<Box<dyn InputPin>> {
+        let pin = self.get(pin)?;
+        Ok(Box::new(match pull {
+            PullUpDown::Off => pin.into_input(),
+            PullUpDown::PullDown => pin.into_input_pulldown(),
+            PullUpDown::PullUp => pin.into_input_pullup(),
+        }))
+    }
+
+    fn output(&self, pin: u8) -> Result<Box<dyn OutputPin>> {
+        Ok(Box::new(self.get(pin)?.into_io(Mode::Output)))
+    }
+
+    /// Uses the hardware PWM channel of the pin if the `pwm` or `pwm-2chan` overlay routes it
+    /// to this pin. Returns `Error::PinBusy` with the other pin of the channel if it is in use.
+    fn hardware_pwm(&self, pin: u8) -> Result<Box<dyn OutputPin>> {
+        static CHANNELS: OnceLock<PwmChannels> = OnceLock::new();
+        let channel = pwm_channel(pin).ok_or(Error::Unsupported("hardware PWM"))?;
+        // Hold the GPIO pin so that no other device uses it while the channel drives it
+        let gpio = self.get(pin)?;
+        if gpio.mode() != pwm_function(pin) {
+            return Err(Error::Unsupported(
+                "hardware PWM on a pin which the PWM overlay doesn't route a channel to",
+            ));
+        }
+        let claim = CHANNELS
+            .get_or_init(PwmChannels::default)
+            .claim(pin, channel)?;
+        let pwm = Pwm::with_frequency(channel, DEFAULT_PWM_FREQUENCY, 0.0, Polarity::Normal, true)?;
+        Ok(Box::new(HardwarePwmPin {
+            gpio,
+            pwm,
+            error: None,
+            _claim: claim,
+        }))
+    }
+}
+
+/// A pin driven by one of the hardware PWM channels through rppal's `pwm` module
+#[derive(Debug)]
+struct HardwarePwmPin {
+    gpio: gpio::Pin,
+    pwm: Pwm,
+    /// An error from `set_high` or `set_low`, which can't return it, reported by the next
+    /// PWM call
+    error: Option<Error>,
+    // Only held to release the channel once the PWM is disabled
+    _claim: PwmChannelClaim,
+}
+
+impl HardwarePwmPin {
+    fn set_duty_cycle(&mut self, duty_cycle: f64) -> Result<()> {
+        let frequency = match self.pwm.frequency()? {
+            frequency if frequency > 0.0 => frequency,
+            _ => DEFAULT_PWM_FREQUENCY,
+        };
+        self.set_pwm_frequency(frequency, duty_cycle)
+    }
+
+    /// Returns the error of a previous `set_high` or `set_low`, if any
+    fn take_error(&mut self) -> Result<()> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+}
+
+impl Pin for HardwarePwmPin {
+    fn pin(&self) -> u8 {
+        self.gpio.pin()
+    }
+
+    fn read(&self) -> Level {
+        self.gpio.read().into()
+    }
+}
+
+impl OutputPin for HardwarePwmPin {
+    /// Devices only drive hardware PWM pins with the PWM methods, which report errors.
+    /// An error here is returned by the next PWM call instead.
+    fn set_high(&mut self) {
+        if let Err(error) = self.set_duty_cycle(1.0) {
+            self.error = Some(error);
+        }
+    }
+
+    fn set_low(&mut self) {
+        if let Err(error) = self.set_duty_cycle(0.0) {
+            self.error = Some(error);
+        }
+    }
+
+    fn set_pwm(&mut self, period: Duration, pulse_width: Duration) -> Result<()> {
+        // The pulse width can't be longer than the period while either is changed
+        self.pwm.set_pulse_width(Duration::from_secs(0))?;
+        self.pwm.set_period(period)?;
+        self.pwm.set_pulse_width(pulse_width)?;
+        self.pwm.enable()?;
+        self.take_error()
+    }
+
+    fn set_pwm_frequency(&mut self, frequency: f64, duty_cycle: f64) -> Result<()> {
+        self.pwm.set_frequency(frequency, duty_cycle)?;
+        self.pwm.enable()?;
+        self.take_error()
+    }
+
+    fn clear_pwm(&mut self) -> Result<()> {
+        self.pwm.set_pulse_width(Duration::from_secs(0))?;
+        self.pwm.disable()?;
+        self.take_error()
+    }
+}
+
+impl Pin for gpio::Pin {
+    fn pin(&self) -> u8 {
+        gpio::Pin::pin(self)
+    }
+
+    fn read(&self) -> Level {
+        gpio::Pin::read(self).into()
+    }
+}
+
+impl Pin for gpio::InputPin {
+    fn pin(&self) -> u8 {
+        gpio::InputPin::pin(self)
+    }
+
+    fn read(&self) -> Level {
+        gpio::InputPin::read(self).into()
+    }
+}
+
+impl InputPin for gpio::InputPin {
+    fn set_interrupt(&mut self, trigger: Trigger) -> Result<()> {
+        Ok(gpio::InputPin::set_interrupt(self, trigger.into())?)
+    }
+
+    fn poll_interrupt(&mut self, reset: bool, timeout: Option<Duration>) -> Result<Option<Level>> {
+        let level = gpio::InputPin::poll_interrupt(self, reset, timeout)?;
+        Ok(level.map(Level::from))
+    }
+
+    fn set_async_interrupt(
+        &mut self,
+        trigger: Trigger,
+        mut callback: Box<dyn FnMut(Level) + Send>,
+    ) -> Result<()> {
+        Ok(gpio::InputPin::set_async_interrupt(
+            self,
+            trigger.into(),
+            move |level| callback(level.into()),
+        )?)
+    }
+
+    fn clear_async_interrupt(&mut self) -> Result<()> {
+        Ok(gpio::InputPin::clear_async_interrupt(self)?)
+    }
+}
+
+impl Pin for gpio::IoPin {
+    fn pin(&self) -> u8 {
+        gpio::IoPin::pin(self)
+    }
+
+    fn read(&self) -> Level {
+        gpio::IoPin::read(self).into()
+    }
+}
+
+impl OutputPin for gpio::IoPin {
+    fn set_high(&mut self) {
+        gpio::IoPin::set_high(self)
+    }
+
+    fn set_low(&mut self) {
+        gpio::IoPin::set_low(self)
+    }
+
+    fn set_pwm(&mut self, period: Duration, pulse_width: Duration) -> Result<()> {
+        Ok(gpio::IoPin::set_pwm(self, period, pulse_width)?)
+    }
+
+    fn set_pwm_frequency(&mut self, frequency: f64, duty_cycle: f64) -> Result<()> {
+        Ok(gpio::IoPin::set_pwm_frequency(self, frequency, duty_cycle)?)
+    }
+
+    fn clear_pwm(&mut self) -> Result<()> {
+        Ok(gpio::IoPin::clear_pwm(self)?)
+    }
+}
@@ -0,0 +1,310 @@
+//! Shared background thread running the blink, beep, pulse and melody patterns of all devices
+use crate::error::{Error, Result};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+/// A background pattern, advanced one step at a time by the scheduler thread
+pub(crate) trait Task: Send {
+    /// Performs the next step of the task, and returns when the following step is due,
+    /// or `None` once the task is finished. An error stops the task without completing it.
+    fn step(&mut self) -> Result<Option<Instant>>;
+}
+
+/// Progress of a scheduled task, shared with its `TaskHandle` and `BlinkHandle`s
+#[derive(Default)]
+struct TaskState {
+    cancelled: AtomicBool,
+    outcome: Mutex<Outcome>,
+    done: Condvar,
+}
+
+#[derive(Default)]
+struct Outcome {
+    finished: bool,
+    completed: bool,
+    error: Option<Error>,
+    callbacks: Vec<Box<dyn FnOnce() + Send>>,
+}
+
+impl TaskState {
+    /// Marks the task as finished, running the completion callbacks if it wasn't cancelled
+    /// and didn't fail
+    fn finish(&self, completed: bool, error: Option<Error>) {
+        let completed = completed && error.is_none();
+        let callbacks = {
+            let mut outcome = self.outcome.lock().unwrap();
+            if outcome.finished {
+                return;
+            }
+            outcome.finished = true;
+            outcome.completed = completed;
+            outcome.error = error;
+            std::mem::take(&mut outcome.callbacks)
+        };
+        self.done.notify_all();
+        if completed {
+            for callback in callbacks {
+                callback();
+            }
+        }
+    }
+}
+
+struct Entry {
+    task: Box<dyn Task>,
+    state: Arc<TaskState>,
+}
+
+#[derive(Default)]
+struct Queue {
+    /// Due time of the next step of each task, earliest first
+    deadlines: BinaryHeap<Reverse<(Instant, u64)>>,
+    /// Tasks waiting for their next step. A task being stepped is out of the map, and a
+    /// cancelled task is removed from it, leaving a stale deadline which is skipped.
+    tasks: HashMap<u64, Entry>,
+    next_id: u64,
+}
+
+struct Scheduler {
+    queue: Mutex<Queue>,
+    changed: Condvar,
+}
+
+impl Scheduler {
+    fn run(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            let (deadline, id) = match queue.deadlines.peek() {
+                Some(&Reverse(next)) => next,
+                None => {
+                    queue = self.changed.wait(queue).unwrap();
+                    continue;
+                }
+            };
+            let now = Instant::now();
+            if deadline > now {
+                queue = self.changed.wait_timeout(queue, deadline - now).unwrap().0;
+                continue;
+            }
+            queue.deadlines.pop();
+            let mut entry = match queue.tasks.remove(&id) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            drop(queue);
+            let next = entry.task.step();
+            queue = self.queue.lock().unwrap();
+            let cancelled = entry.state.cancelled.load(Ordering::SeqCst);
+            match next {
+                Ok(Some(deadline)) if !cancelled => {
+                    queue.deadlines.push(Reverse((deadline, id)));
+                    queue.tasks.insert(id, entry);
+                }
+                next => {
+                    drop(queue);
+                    entry.state.finish(!cancelled, next.err());
+                    queue = self.queue.lock().unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// Returns the scheduler, starting its thread on first use
+fn scheduler() -> &'static Scheduler {
+    static SCHEDULER: OnceLock<Scheduler> = OnceLock::new();
+    static STARTED: OnceLock<()> = OnceLock::new();
+    let scheduler = SCHEDULER.get_or_init(|| Scheduler {
+        queue: Mutex::new(Queue::default()),
+        changed: Condvar::new(),
+    });
+    STARTED.get_or_init(|| {
+        let thread = thread::Builder::new()
+            .name("rust_gpiozero scheduler".into())
+            .spawn(move || scheduler.run())
+            .expect("Could not spawn scheduler thread");
+        SCHEDULER_THREAD.get_or_init(|| thread.thread().id());
+    });
+    scheduler
+}
+
+static SCHEDULER_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+/// Returns `true` when called from the scheduler thread, for example from a hold or gesture
+/// callback, or a completion callback
+fn on_scheduler_thread() -> bool {
+    SCHEDULER_THREAD.get() == Some(&thread::current().id())
+}
+
+/// Longest time accepted for a step of a pattern, so that its deadline can't overflow
+pub(crate) const MAX_TIME: Duration = Duration::from_secs(u32::MAX as u64);
+
+/// Converts a time in seconds given to a device, treating negative and NaN times as 0 and
+/// saturating longer times, including infinity, at `MAX_TIME`.
+pub(crate) fn saturating_seconds(seconds: f32) -> Duration {
+    if seconds.is_nan() || seconds <= 0.0 {
+        return Duration::ZERO;
+    }
+    Duration::try_from_secs_f32(seconds).map_or(MAX_TIME, |time| time.min(MAX_TIME))
+}
+
+/// The instant all periodic patterns are aligned to, so that patterns with the same period
+/// run in lock-step
+pub(crate) fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+/// Runs `task` on the shared scheduler thread, starting immediately.
+pub(crate) fn schedule<T: Task + 'static>(task: T) -> TaskHandle {
+    schedule_at(Instant::now(), task)
+}
+
+/// Runs `task` on the shared scheduler thread, with its first step due at `start`.
+pub(crate) fn schedule_at<T: Task + 'static>(start: Instant, task: T) -> TaskHandle {
+    let scheduler = scheduler();
+    let state = Arc::new(TaskState::default());
+    let mut queue = scheduler.queue.lock().unwrap();
+    let id = queue.next_id;
+    queue.next_id += 1;
+    queue.deadlines.push(Reverse((start, id)));
+    queue.tasks.insert(
+        id,
+        Entry {
+            task: Box::new(task),
+            state: Arc::clone(&state),
+        },
+    );
+    scheduler.changed.notify_one();
+    TaskHandle { id, state }
+}
+
+/// Controls a task running on the scheduler thread
+#[derive(Debug)]
+pub(crate) struct TaskHandle {
+    id: u64,
+    state: Arc<TaskState>,
+}
+
+impl TaskHandle {
+    /// Stops the task without waiting for its next step to be due. If the task is in the
+    /// middle of a step, blocks until the step is done, so that the task doesn't write to
+    /// its device after this returns.
+    ///
+    /// On the scheduler thread no other step can be in progress, so this doesn't wait, which
+    /// would deadlock when a task cancels itself from a callback.
+    pub(crate) fn cancel(&self) {
+        self.abort();
+        if !on_scheduler_thread() {
+            self.completion().wait();
+        }
+    }
+
+    /// Stops the task like `cancel`, but without waiting for a step in progress, so that it
+    /// can be called from the scheduler thread.
+    pub(crate) fn abort(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        let scheduler = scheduler();
+        let removed = scheduler.queue.lock().unwrap().tasks.remove(&self.id);
+        if removed.is_some() {
+            self.state.finish(false, None);
+        }
+    }
+
+    /// Returns a handle to wait for the task to finish.
+    pub(crate) fn completion(&self) -> BlinkHandle {
+        BlinkHandle(Arc::clone(&self.state))
+    }
+}
+
+/// Tracks a pattern running in the background (blinking, beeping, pulsing or playing a
+/// melody), to wait for it to finish or be notified when it does. The pattern is still
+/// controlled by its device, which cancels it when given another state, or dropped.
+#[derive(Debug, Clone)]
+pub struct BlinkHandle(Arc<TaskState>);
+
+impl BlinkHandle {
+    /// Blocks until the pattern has finished all its blinks, or was cancelled.
+    /// Never returns for an endless pattern which isn't cancelled.
+    ///
+    /// # Deadlocks
+    ///
+    /// Patterns run on the same thread as the hold, gesture and `on_completed` callbacks, so
+    /// waiting from one of those callbacks would never return. This returns immediately
+    /// instead when called from such a callback; use `on_completed` to chain patterns there.
+    pub fn wait(&self) {
+        if on_scheduler_thread() {
+            return;
+        }
+        let outcome = self.0.outcome.lock().unwrap();
+        let _outcome = self
+            .0
+            .done
+            .wait_while(outcome, |outcome| !outcome.finished)
+            .unwrap();
+    }
+
+    /// Like `wait`, but gives up after `timeout`. Returns `true` if the pattern is finished.
+    /// Like `wait`, returns immediately when called from a callback on the scheduler thread.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        if on_scheduler_thread() {
+            return self.is_finished();
+        }
+        let outcome = self.0.outcome.lock().unwrap();
+        let (outcome, _) = self
+            .0
+            .done
+            .wait_timeout_while(outcome, timeout, |outcome| !outcome.finished)
+            .unwrap();
+        outcome.finished
+    }
+
+    /// Returns `true` once the pattern has finished all its blinks or was cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.0.outcome.lock().unwrap().finished
+    }
+
+    /// Returns `true` if the pattern has finished all its blinks, rather than being cancelled
+    /// or stopped by an error.
+    pub fn is_completed(&self) -> bool {
+        self.0.outcome.lock().unwrap().completed
+    }
+
+    /// Returns the error which stopped the pattern, if writing to the device failed.
+    /// The error is only returned once, to the first caller.
+    pub fn take_error(&self) -> Option<Error> {
+        self.0.outcome.lock().unwrap().error.take()
+    }
+
+    /// Calls `callback` when the pattern has finished all its blinks. The callback runs on the
+    /// shared scheduler thread, so it should return quickly. It is called immediately if the
+    /// pattern has already completed, and never if the pattern is cancelled or fails.
+    pub fn on_completed<C>(&self, callback: C)
+    where
+        C: FnOnce() + Send + 'static,
+    {
+        let mut outcome = self.0.outcome.lock().unwrap();
+        if !outcome.finished {
+            outcome.callbacks.push(Box::new(callback));
+        } else if outcome.completed {
+            drop(outcome);
+            callback();
+        }
+    }
+}
+
+impl fmt::Debug for TaskState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = self.outcome.lock().unwrap();
+        f.debug_struct("TaskState")
+            .field("finished", &outcome.finished)
+            .field("completed", &outcome.completed)
+            .field("error", &outcome.error)
+            .finish()
+    }
+}
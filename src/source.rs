@@ -0,0 +1,138 @@
+//! Background pump that feeds the values of a source into a device
+use crate::error::{Error, Result};
+use crate::scheduler;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+/// Default delay between two values read from a source, in seconds
+pub(crate) const DEFAULT_SOURCE_DELAY: f32 = 0.01;
+
+/// Reads values from an iterator in a background thread and hands each of them to a writer,
+/// waiting `delay` between values. The pump stops when the iterator is exhausted, when the
+/// writer fails, or when it is stopped or dropped.
+#[derive(Debug)]
+pub(crate) struct SourcePump {
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SourcePump {
+    pub(crate) fn start<I, F>(source: I, delay: f32, mut write: F) -> SourcePump
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+        F: FnMut(I::Item) -> Result<()> + Send + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        let mut values = source.into_iter();
+        let delay = scheduler::saturating_seconds(delay);
+
+        let flag = Arc::clone(&running);
+        let handle = thread::spawn(move || {
+            while flag.load(Ordering::SeqCst) {
+                match values.next() {
+                    Some(value) => {
+                        if write(value).is_err() {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+                // Parked rather than asleep, so that `stop` can wake the thread up
+                let deadline = Instant::now() + delay;
+                while flag.load(Ordering::SeqCst) {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break;
+                    }
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        });
+
+        SourcePump {
+            running,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stops the pump and waits for the background thread to finish.
+    pub(crate) fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            // A panicking source or writer has already stopped the pump
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for SourcePump {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Checks a source delay in seconds, which can't be negative or infinite
+pub(crate) fn check_delay(seconds: f32) -> Result<()> {
+    if !(seconds >= 0.0 && seconds.is_finite()) {
+        return Err(Error::OutOfRange {
+            value: f64::from(seconds),
+            min: 0.0,
+            max: f64::INFINITY,
+        });
+    }
+    Ok(())
+}
+
+/// Adds `set_source`, `clear_source` and the source delay property to a device with
+/// `source: Mutex<Option<SourcePump>>` and `source_delay: f32` fields. The source is behind a
+/// mutex so that `clear_source` can be called from the `&self` methods setting a new state.
+/// The device must provide a `source_writer` method returning the function applying each value.
+macro_rules! impl_source {
+    () => {
+        /// Continuously feed the values of `source` (e.g. the `values()` of another device)
+        /// into this device from a background thread, until the source is exhausted or
+        /// `clear_source` is called. Replaces any previously set source.
+        pub fn set_source<I>(&mut self, source: I)
+        where
+            I: IntoIterator,
+            I::IntoIter: Send + 'static,
+            I::Item: Into<f64>,
+        {
+            self.clear_source();
+            let mut write = self.source_writer();
+            *self.source.get_mut().unwrap() = Some($crate::source::SourcePump::start(
+                source,
+                self.source_delay,
+                move |value: I::Item| write(value.into()),
+            ));
+        }
+
+        /// Stop feeding values from the source set with `set_source`.
+        pub fn clear_source(&self) {
+            // Taken out first, so that the lock isn't held while the thread is joined
+            let source = self.source.lock().unwrap().take();
+            if let Some(mut source) = source {
+                source.stop();
+            }
+        }
+
+        /// The delay in seconds between two values read from the source. Defaults to 0.01.
+        pub fn source_delay(&self) -> f32 {
+            self.source_delay
+        }
+
+        /// Set the delay in seconds between two values read from the source.
+        /// Takes effect the next time `set_source` is called.
+        /// Returns `Error::OutOfRange` if the delay is negative or infinite.
+        pub fn set_source_delay(&mut self, seconds: f32) -> $crate::error::Result<()> {
+            $crate::source::check_delay(seconds)?;
+            self.source_delay = seconds;
+            Ok(())
+        }
+    };
+}
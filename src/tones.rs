@@ -0,0 +1,165 @@
+//! Musical tones for devices like `TonalBuzzer`
+//!
+//! A `Tone` is a frequency, which can be given directly in Hz, as a MIDI note number, or as a
+//! note name like `"A4"` or `"C#5"`.
+//!
+//! # Example
+//!
+//! ```
+//! use rust_gpiozero::tones::Tone;
+//!
+//! let a4: Tone = "A4".parse().unwrap();
+//! assert_eq!(a4.frequency(), 440.0);
+//! assert_eq!(a4, "69".parse().unwrap());
+//! assert_eq!(a4, "440 Hz".parse().unwrap());
+//! assert_eq!(a4.up(3).note(), Some("C5".to_string()));
+//! ```
+use crate::error::{Error, Result};
+use std::str::FromStr;
+
+/// Frequency of A4 (MIDI note 69), the reference for all other notes
+const A4_FREQUENCY: f64 = 440.0;
+const A4_MIDI: i32 = 69;
+
+/// Names of the notes of an octave, starting from C
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// A musical tone, stored as its frequency in Hz.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Tone {
+    frequency: f64,
+}
+
+impl Tone {
+    /// Returns the tone with the given frequency in Hz.
+    /// Returns `Error::OutOfRange` if the frequency isn't positive.
+    pub fn from_frequency(frequency: f64) -> Result<Tone> {
+        if !(frequency > 0.0 && frequency.is_finite()) {
+            return Err(Error::OutOfRange {
+                value: frequency,
+                min: 0.0,
+                max: f64::INFINITY,
+            });
+        }
+        Ok(Tone { frequency })
+    }
+
+    /// Returns the tone of a MIDI note number, where 60 is middle C (C4) and 69 is A4.
+    /// Returns `Error::OutOfRange` if the number is greater than 127.
+    pub fn from_midi(midi: u8) -> Result<Tone> {
+        if midi > 127 {
+            return Err(Error::OutOfRange {
+                value: f64::from(midi),
+                min: 0.0,
+                max: 127.0,
+            });
+        }
+        Ok(Tone::from_semitones(i32::from(midi) - A4_MIDI))
+    }
+
+    /// Returns the tone of a note name: a letter from A to G, an optional sharp (`#` or `♯`) or
+    /// flat (`b` or `♭`), and an octave from -1 to 9, e.g. `"A4"`, `"C#5"` or `"Bb3"`.
+    /// Returns `Error::InvalidTone` if the name can't be parsed, or `Error::OutOfRange` if the
+    /// note is outside the MIDI range (C-1 to G9).
+    pub fn from_note(note: &str) -> Result<Tone> {
+        let invalid = || Error::InvalidTone(note.to_string());
+        let mut chars = note.trim().chars();
+        let semitone = match chars.next().map(|letter| letter.to_ascii_uppercase()) {
+            Some('C') => 0,
+            Some('D') => 2,
+            Some('E') => 4,
+            Some('F') => 5,
+            Some('G') => 7,
+            Some('A') => 9,
+            Some('B') => 11,
+            _ => return Err(invalid()),
+        };
+        let rest = chars.as_str();
+        let (accidental, octave) = if let Some(octave) = rest.strip_prefix(['#', '♯']) {
+            (1, octave)
+        } else if let Some(octave) = rest.strip_prefix(['b', '♭']) {
+            (-1, octave)
+        } else {
+            (0, rest)
+        };
+        let octave: i32 = octave.parse().map_err(|_| invalid())?;
+        let midi = (octave + 1) * 12 + semitone + accidental;
+        if !(0..=127).contains(&midi) {
+            return Err(Error::OutOfRange {
+                value: f64::from(midi),
+                min: 0.0,
+                max: 127.0,
+            });
+        }
+        Ok(Tone::from_semitones(midi - A4_MIDI))
+    }
+
+    /// Returns the tone `semitones` away from A4
+    fn from_semitones(semitones: i32) -> Tone {
+        Tone {
+            frequency: A4_FREQUENCY * 2f64.powf(f64::from(semitones) / 12.0),
+        }
+    }
+
+    /// The frequency of the tone in Hz
+    pub fn frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// The number of the MIDI note closest to the tone, or `None` if it is outside the MIDI
+    /// range.
+    pub fn midi(&self) -> Option<u8> {
+        let midi = (f64::from(A4_MIDI) + 12.0 * (self.frequency / A4_FREQUENCY).log2()).round();
+        if (0.0..=127.0).contains(&midi) {
+            Some(midi as u8)
+        } else {
+            None
+        }
+    }
+
+    /// The name of the note closest to the tone, using sharps (e.g. `"C#5"`), or `None` if it
+    /// is outside the MIDI range.
+    pub fn note(&self) -> Option<String> {
+        self.midi().map(|midi| {
+            let octave = i32::from(midi) / 12 - 1;
+            format!("{}{}", NOTE_NAMES[usize::from(midi % 12)], octave)
+        })
+    }
+
+    /// Returns the tone `semitones` higher than this one.
+    pub fn up(&self, semitones: i32) -> Tone {
+        Tone {
+            frequency: self.frequency * 2f64.powf(f64::from(semitones) / 12.0),
+        }
+    }
+
+    /// Returns the tone `semitones` lower than this one.
+    pub fn down(&self, semitones: i32) -> Tone {
+        self.up(-semitones)
+    }
+}
+
+impl FromStr for Tone {
+    type Err = Error;
+
+    /// Parses a frequency ending with `Hz` (e.g. `"440Hz"` or `"440 Hz"`), a MIDI note number
+    /// (e.g. `"69"`) or a note name (e.g. `"A4"`).
+    fn from_str(s: &str) -> Result<Tone> {
+        let s = s.trim();
+        let lower = s.to_ascii_lowercase();
+        if let Some(frequency) = lower.strip_suffix("hz") {
+            let frequency = frequency
+                .trim()
+                .parse()
+                .map_err(|_| Error::InvalidTone(s.to_string()))?;
+            Tone::from_frequency(frequency)
+        } else if s.starts_with(|c: char| c.is_ascii_digit()) {
+            let midi = s.parse().map_err(|_| Error::InvalidTone(s.to_string()))?;
+            Tone::from_midi(midi)
+        } else {
+            Tone::from_note(s)
+        }
+    }
+}
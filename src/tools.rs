@@ -0,0 +1,331 @@
+//! Source tools for combining and transforming device values
+//!
+//! Each tool takes one or more iterators of values (such as the `values()` of an input device,
+//! or another tool) and returns a new iterator, which can be passed to `set_source` on an
+//! output device. Values are read as `f64`, so `bool` values from digital inputs like
+//! `Button` are `0.0` or `1.0`, and analog values like those accepted by
+//! `PWMOutputDevice::set_value` are used as they are.
+//!
+//! # Example
+//!
+//! Light an LED while a button is *not* pressed:
+//!
+//! ```
+//! use rust_gpiozero::pins::{self, MockFactory};
+//! use rust_gpiozero::tools::negated;
+//! use rust_gpiozero::*;
+//! use std::time::Duration;
+//!
+//! let factory = MockFactory::new();
+//! pins::set_default_factory(factory.clone());
+//!
+//! let button = Button::new_with_pulldown(17);
+//! let mut led = LED::new(18);
+//! led.set_source(negated(button.values()));
+//!
+//! assert!(factory.get(18).wait_for_value(1.0, Duration::from_secs(1)));
+//! factory.get(17).drive_high();
+//! assert!(factory.get(18).wait_for_value(0.0, Duration::from_secs(1)));
+//! led.clear_source();
+//! ```
+//!
+//! Tools can be chained, and generators provide values without any input device:
+//!
+//! ```
+//! use rust_gpiozero::tools::{clamped, ramping_values, scaled};
+//!
+//! // Map a ramp from [0, 1] to [-1, 1] and keep only its positive half
+//! let values: Vec<f64> = clamped(scaled(ramping_values(4), -1.0, 1.0, 0.0, 1.0), 0.0, 1.0)
+//!     .take(4)
+//!     .collect();
+//! assert_eq!(values, vec![0.0, 0.0, 1.0, 0.0]);
+//! ```
+use crate::scheduler;
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::hash::{BuildHasher, Hasher};
+use std::iter;
+use std::thread;
+
+/// Returns the logical negation of `values`: `true` for zero (or `false`) values,
+/// `false` for any other value.
+pub fn negated<I>(values: I) -> impl Iterator<Item = bool>
+where
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    values.into_iter().map(|value| value.into() == 0.0)
+}
+
+/// Returns `values` inverted within the range `output_min..=output_max`, so that
+/// `output_min` becomes `output_max` and vice versa.
+///
+/// # Panics
+///
+/// Panics if `output_min` is not lower than `output_max`.
+pub fn inverted<I>(values: I, output_min: f64, output_max: f64) -> impl Iterator<Item = f64>
+where
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    assert!(
+        output_min < output_max,
+        "output_min must be lower than output_max"
+    );
+    values
+        .into_iter()
+        .map(move |value| output_min + output_max - value.into())
+}
+
+/// Returns `values` scaled linearly from the range `input_min..=input_max` to the range
+/// `output_min..=output_max`. Values outside the input range are scaled beyond the output
+/// range; combine with `clamped` to limit them.
+///
+/// # Panics
+///
+/// Panics if `input_min` is not lower than `input_max`.
+pub fn scaled<I>(
+    values: I,
+    output_min: f64,
+    output_max: f64,
+    input_min: f64,
+    input_max: f64,
+) -> impl Iterator<Item = f64>
+where
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    assert!(
+        input_min < input_max,
+        "input_min must be lower than input_max"
+    );
+    let input_size = input_max - input_min;
+    let output_size = output_max - output_min;
+    values
+        .into_iter()
+        .map(move |value| output_min + ((value.into() - input_min) / input_size) * output_size)
+}
+
+/// Returns `values` limited to the range `output_min..=output_max`.
+///
+/// # Panics
+///
+/// Panics if `output_min` is greater than `output_max`.
+pub fn clamped<I>(values: I, output_min: f64, output_max: f64) -> impl Iterator<Item = f64>
+where
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    assert!(
+        output_min <= output_max,
+        "output_min must not be greater than output_max"
+    );
+    values
+        .into_iter()
+        .map(move |value| value.into().clamp(output_min, output_max))
+}
+
+/// Returns the absolute value of `values`.
+pub fn absoluted<I>(values: I) -> impl Iterator<Item = f64>
+where
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    values.into_iter().map(|value| value.into().abs())
+}
+
+/// Returns the moving average of the last `qsize` values. Nothing is returned until
+/// `qsize` values have been read.
+///
+/// # Panics
+///
+/// Panics if `qsize` is 0.
+pub fn smoothed<I>(values: I, qsize: usize) -> impl Iterator<Item = f64>
+where
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    assert!(qsize > 0, "qsize must be greater than 0");
+    let mut values = values.into_iter();
+    let mut queue = VecDeque::with_capacity(qsize);
+    iter::from_fn(move || {
+        while queue.len() < qsize {
+            queue.push_back(values.next()?.into());
+        }
+        let mean = queue.iter().sum::<f64>() / qsize as f64;
+        queue.pop_front();
+        Some(mean)
+    })
+}
+
+/// Returns `values` delayed by `qsize` values: the first `qsize` values are queued,
+/// then each new value read returns the oldest one in the queue.
+///
+/// # Panics
+///
+/// Panics if `qsize` is 0.
+pub fn queued<I>(values: I, qsize: usize) -> impl Iterator<Item = I::Item>
+where
+    I: IntoIterator,
+{
+    assert!(qsize > 0, "qsize must be greater than 0");
+    let mut values = values.into_iter();
+    let mut queue = VecDeque::with_capacity(qsize + 1);
+    iter::from_fn(move || {
+        while queue.len() <= qsize {
+            queue.push_back(values.next()?);
+        }
+        queue.pop_front()
+    })
+}
+
+/// Returns `values`, waiting `delay` seconds after each value before reading the next one.
+/// Negative delays are treated as 0, and an infinite delay never reads a second value.
+pub fn post_delayed<I>(values: I, delay: f32) -> impl Iterator<Item = I::Item>
+where
+    I: IntoIterator,
+{
+    let delay = scheduler::saturating_seconds(delay);
+    let mut values = values.into_iter();
+    let mut first = true;
+    iter::from_fn(move || {
+        if !first {
+            thread::sleep(delay);
+        }
+        first = false;
+        values.next()
+    })
+}
+
+/// Reads one value from each of `sources` at a time, stopping as soon as any source is
+/// exhausted. Sources with different iterator types can be combined by boxing them as
+/// `Box<dyn Iterator<Item = f64> + Send>`.
+fn combined<S, I>(sources: S) -> impl Iterator<Item = Vec<f64>>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    let mut sources: Vec<I::IntoIter> = sources.into_iter().map(IntoIterator::into_iter).collect();
+    iter::from_fn(move || {
+        if sources.is_empty() {
+            return None;
+        }
+        sources
+            .iter_mut()
+            .map(|source| source.next().map(Into::into))
+            .collect()
+    })
+}
+
+/// Returns `true` while all of `sources` have non-zero (or `true`) values, like a logical AND.
+pub fn all_values<S, I>(sources: S) -> impl Iterator<Item = bool>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    combined(sources).map(|values| values.iter().all(|&value| value != 0.0))
+}
+
+/// Returns `true` while any of `sources` has a non-zero (or `true`) value, like a logical OR.
+pub fn any_values<S, I>(sources: S) -> impl Iterator<Item = bool>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    combined(sources).map(|values| values.iter().any(|&value| value != 0.0))
+}
+
+/// Returns the mean of the values of `sources`.
+pub fn averaged<S, I>(sources: S) -> impl Iterator<Item = f64>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    combined(sources).map(|values| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Returns the product of the values of `sources`.
+pub fn multiplied<S, I>(sources: S) -> impl Iterator<Item = f64>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    combined(sources).map(|values| values.iter().product())
+}
+
+/// Returns the sum of the values of `sources`.
+pub fn summed<S, I>(sources: S) -> impl Iterator<Item = f64>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    combined(sources).map(|values| values.iter().sum())
+}
+
+/// Returns an endless sine wave between -1 and 1, repeating every `period` values.
+///
+/// # Panics
+///
+/// Panics if `period` is 0.
+pub fn sin_values(period: usize) -> impl Iterator<Item = f64> {
+    assert!(period > 0, "period must be greater than 0");
+    (0..period)
+        .map(move |i| (2.0 * PI * i as f64 / period as f64).sin())
+        .cycle()
+}
+
+/// Returns an endless cosine wave between -1 and 1, repeating every `period` values.
+///
+/// # Panics
+///
+/// Panics if `period` is 0.
+pub fn cos_values(period: usize) -> impl Iterator<Item = f64> {
+    assert!(period > 0, "period must be greater than 0");
+    (0..period)
+        .map(move |i| (2.0 * PI * i as f64 / period as f64).cos())
+        .cycle()
+}
+
+/// Returns an endless ramp rising from 0 to 1 and falling back to 0, repeating every
+/// `period` values.
+///
+/// # Panics
+///
+/// Panics if `period` is 0.
+pub fn ramping_values(period: usize) -> impl Iterator<Item = f64> {
+    assert!(period > 0, "period must be greater than 0");
+    let step = 2.0 / period as f64;
+    (0..period)
+        .map(move |i| {
+            let value = i as f64 * step;
+            if value > 1.0 {
+                2.0 - value
+            } else {
+                value
+            }
+        })
+        .cycle()
+}
+
+/// Returns endless random values between 0 (inclusive) and 1 (exclusive).
+pub fn random_values() -> impl Iterator<Item = f64> {
+    // xorshift64*, seeded from the randomly keyed std hasher
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    let mut state = hasher.finish() | 1;
+    iter::repeat_with(move || {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        let value = state.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        // Keep the 53 high bits, the precision of an f64
+        (value >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
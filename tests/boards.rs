@@ -0,0 +1,122 @@
+mod common;
+
+use rust_gpiozero::{Error, LEDBoard, Robot, TrafficLights, TrafficPhase};
+use std::iter;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn set_value_rejects_wrong_count() {
+    let factory = common::factory();
+    let mut board = LEDBoard::new(&[2, 3]);
+    assert!(matches!(
+        board.set_value(&[1.0]),
+        Err(Error::WrongValueCount {
+            expected: 2,
+            actual: 1
+        })
+    ));
+    board.set_value(&[1.0, 0.0]).unwrap();
+    assert_eq!(factory.get(2).value(), 1.0);
+    assert_eq!(factory.get(3).value(), 0.0);
+}
+
+#[test]
+fn dropped_robot_stops_source() {
+    let factory = common::factory();
+    let mut robot = Robot::new((4, 5), (6, 7));
+    robot.set_source(iter::repeat((0.5, -0.5)));
+    assert!(factory.get(4).wait_for_value(0.5, Duration::from_secs(1)));
+    drop(robot);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(factory.get(4).value(), 0.0);
+    assert_eq!(factory.get(7).value(), 0.0);
+}
+
+#[test]
+fn set_value_validates_every_value_first() {
+    let factory = common::factory();
+    let mut board = LEDBoard::new(&[8, 9]);
+    assert!(matches!(
+        board.set_value(&[1.0, 0.5]),
+        Err(Error::Unsupported(_))
+    ));
+    assert!(matches!(
+        board.set_value(&[1.0, 2.0]),
+        Err(Error::OutOfRange { .. })
+    ));
+    assert_eq!(factory.get(8).value(), 0.0);
+    assert_eq!(factory.get(9).value(), 0.0);
+}
+
+#[test]
+fn named_board_rejects_duplicate_names() {
+    let _factory = common::factory();
+    assert!(matches!(
+        LEDBoard::try_new_named(&[("red", 10), ("red", 11)]),
+        Err(Error::DuplicateName(name)) if name == "red"
+    ));
+    // The pins are released again
+    let board = LEDBoard::try_new_named(&[("red", 10), ("green", 11)]).unwrap();
+    assert!(board.named("green").is_some());
+}
+
+#[test]
+fn robot_turns_reject_negative_speeds() {
+    let _factory = common::factory();
+    let mut robot = Robot::new((12, 13), (14, 15));
+    for speed in [-0.5, 1.5] {
+        assert!(matches!(
+            robot.left(speed),
+            Err(Error::OutOfRange { min, max, .. }) if min == 0.0 && max == 1.0
+        ));
+        assert!(matches!(robot.right(speed), Err(Error::OutOfRange { .. })));
+    }
+    assert_eq!(robot.value(), (0.0, 0.0));
+    robot.left(0.5).unwrap();
+    assert_eq!(robot.value(), (-0.5, 0.5));
+}
+
+#[test]
+fn traffic_lights_advance_through_the_phases() {
+    let factory = common::factory();
+    let mut lights = TrafficLights::new(16, 17, 18);
+    assert_eq!(lights.phase(), None);
+    assert_eq!(lights.advance().unwrap(), TrafficPhase::Stop);
+    for &(phase, value) in &[
+        (TrafficPhase::Ready, (1.0, 1.0, 0.0)),
+        (TrafficPhase::Go, (0.0, 0.0, 1.0)),
+        (TrafficPhase::Caution, (0.0, 1.0, 0.0)),
+        (TrafficPhase::Stop, (1.0, 0.0, 0.0)),
+    ] {
+        assert_eq!(lights.advance().unwrap(), phase);
+        assert_eq!(lights.phase(), Some(phase));
+        assert_eq!(lights.value(), value);
+        assert_eq!(factory.get(16).value(), value.0);
+        assert_eq!(factory.get(17).value(), value.1);
+        assert_eq!(factory.get(18).value(), value.2);
+    }
+
+    // Lights outside the sequence start it again
+    lights.set_value((1.0, 0.0, 1.0)).unwrap();
+    assert_eq!(lights.phase(), None);
+    assert_eq!(lights.advance().unwrap(), TrafficPhase::Stop);
+}
+
+#[test]
+fn traffic_lights_yellow_is_amber() {
+    let factory = common::factory();
+    let mut lights = TrafficLights::new(19, 20, 21);
+    lights.yellow_mut().on().unwrap();
+    assert_eq!(factory.get(20).value(), 1.0);
+    assert!(lights.amber().is_lit());
+    lights
+        .board_mut()
+        .named_mut("yellow")
+        .unwrap()
+        .off()
+        .unwrap();
+    assert!(!lights.yellow().is_lit());
+    assert_eq!(factory.get(20).value(), 0.0);
+    assert_eq!(lights.board().named("amber").unwrap().value(), 0.0);
+}
@@ -0,0 +1,15 @@
+use rust_gpiozero::pins::{self, MockFactory};
+use std::sync::OnceLock;
+
+/// Returns the mock factory installed as the default factory for all the tests of a file.
+/// Tests run in parallel, so each test must use its own pins.
+pub fn factory() -> MockFactory {
+    static FACTORY: OnceLock<MockFactory> = OnceLock::new();
+    FACTORY
+        .get_or_init(|| {
+            let factory = MockFactory::new();
+            pins::set_default_factory(factory.clone());
+            factory
+        })
+        .clone()
+}
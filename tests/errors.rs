@@ -0,0 +1,39 @@
+mod common;
+
+use rppal::gpio;
+use rust_gpiozero::pins::{PinFactory, RppalFactory};
+use rust_gpiozero::{Button, DigitalInputDevice, Error, OutputDevice, Servo, LED};
+
+#[test]
+fn try_new_reports_busy_pins() {
+    let _factory = common::factory();
+    let led = LED::try_new(2).unwrap();
+    assert!(matches!(Button::try_new(2), Err(Error::PinBusy(2))));
+    assert!(matches!(
+        DigitalInputDevice::try_new(2),
+        Err(Error::PinBusy(2))
+    ));
+    assert!(matches!(OutputDevice::try_new(2), Err(Error::PinBusy(2))));
+    assert!(matches!(Servo::try_new(2), Err(Error::PinBusy(2))));
+    drop(led);
+    assert!(Button::try_new(2).is_ok());
+}
+
+#[test]
+fn rppal_factory_rejects_unknown_pins() {
+    // Checked before accessing the GPIO peripheral, so this runs anywhere
+    let factory = RppalFactory::new();
+    assert!(matches!(factory.output(200), Err(Error::UnknownPin(200))));
+}
+
+#[test]
+fn rppal_errors_are_mapped() {
+    assert!(matches!(
+        Error::from(gpio::Error::UnknownModel),
+        Error::UnsupportedBoard
+    ));
+    assert!(matches!(
+        Error::from(gpio::Error::PermissionDenied("/dev/gpiomem".to_string())),
+        Error::PermissionDenied(path) if path == "/dev/gpiomem"
+    ));
+}
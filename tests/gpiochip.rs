@@ -0,0 +1,71 @@
+//! Tests of the gpiochip backend. Those needing a GPIO chip run against the chip named by
+//! the `GPIOZERO_TEST_CHIP` environment variable, e.g. one created with the `gpio-sim` kernel
+//! module, and are skipped when it isn't set.
+#![cfg(feature = "gpiochip")]
+use rust_gpiozero::pins::{GpiochipFactory, Level, PinFactory, PullUpDown};
+use rust_gpiozero::Error;
+use std::env;
+use std::time::Duration;
+
+fn test_chip() -> Option<GpiochipFactory> {
+    env::var_os("GPIOZERO_TEST_CHIP").map(GpiochipFactory::new)
+}
+
+#[test]
+fn missing_chip_is_an_io_error() {
+    let factory = GpiochipFactory::new("/dev/gpiochip-does-not-exist");
+    assert!(matches!(factory.output(0), Err(Error::Io(_))));
+}
+
+#[test]
+fn other_device_is_unsupported() {
+    let factory = GpiochipFactory::new("/dev/null");
+    assert!(matches!(
+        factory.input(0, PullUpDown::Off),
+        Err(Error::UnsupportedBoard)
+    ));
+}
+
+#[test]
+fn debounce_period_must_fit_the_kernel() {
+    let mut factory = GpiochipFactory::new("/dev/null");
+    assert!(matches!(
+        factory.set_debounce(17, Duration::from_secs(5000)),
+        Err(Error::OutOfRange { .. })
+    ));
+    factory
+        .set_debounce(17, Duration::from_micros(u64::from(u32::MAX)))
+        .unwrap();
+}
+
+#[test]
+fn line_beyond_chip_is_unknown() {
+    let factory = match test_chip() {
+        Some(factory) => factory,
+        None => return,
+    };
+    assert!(matches!(factory.output(255), Err(Error::UnknownPin(255))));
+}
+
+#[test]
+fn requested_line_is_busy() {
+    let factory = match test_chip() {
+        Some(factory) => factory,
+        None => return,
+    };
+    let _pin = factory.output(0).unwrap();
+    assert!(matches!(factory.output(0), Err(Error::PinBusy(0))));
+}
+
+#[test]
+fn output_reads_back() {
+    let factory = match test_chip() {
+        Some(factory) => factory,
+        None => return,
+    };
+    let mut pin = factory.output(1).unwrap();
+    pin.set_high();
+    assert_eq!(pin.read(), Level::High);
+    pin.set_low();
+    assert_eq!(pin.read(), Level::Low);
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use rust_gpiozero::{Button, Debounce, Gesture};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+#[test]
+fn debounced_press_reaches_every_subscriber() {
+    let factory = common::factory();
+    let mut button = Button::new(2).debounce(Duration::from_millis(50));
+    let first = Arc::new(AtomicUsize::new(0));
+    let second = Arc::new(AtomicUsize::new(0));
+    let releases = Arc::new(AtomicUsize::new(0));
+    for counter in [&first, &second] {
+        let counter = Arc::clone(counter);
+        button
+            .when_pressed(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+    }
+    let counter = Arc::clone(&releases);
+    button
+        .when_released(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+
+    // The pin of a pull-up button goes low when it is pressed, and bounces
+    let pin = factory.get(2);
+    pin.drive_low();
+    pin.drive_high();
+    pin.drive_low();
+    assert_eq!(first.load(Ordering::SeqCst), 1);
+    assert_eq!(second.load(Ordering::SeqCst), 1);
+    assert_eq!(releases.load(Ordering::SeqCst), 0);
+
+    std::thread::sleep(Duration::from_millis(60));
+    pin.drive_high();
+    assert_eq!(releases.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn debounced_press_stats_ignore_bounces() {
+    let factory = common::factory();
+    let mut button = Button::new(6).debounce(Duration::from_millis(50));
+    button.set_press_stats(true).unwrap();
+
+    let pin = factory.get(6);
+    for _ in 0..2 {
+        // Each press bounces, and so does its release
+        pin.drive_low();
+        pin.drive_high();
+        pin.drive_low();
+        std::thread::sleep(Duration::from_millis(60));
+        pin.drive_high();
+        pin.drive_low();
+        pin.drive_high();
+        std::thread::sleep(Duration::from_millis(60));
+    }
+    let stats = button.press_stats().unwrap();
+    assert_eq!(stats.count(), 2);
+    assert!(stats.max_duration().unwrap() >= Duration::from_millis(60));
+    assert!(stats.mean_duration().unwrap() >= Duration::from_millis(60));
+}
+
+#[test]
+fn is_held_tracks_presses_from_set_hold_time() {
+    let factory = common::factory();
+    let mut button = Button::new(3);
+    let pin = factory.get(3);
+
+    // Getters don't start tracking presses
+    pin.drive_low();
+    std::thread::sleep(Duration::from_millis(30));
+    assert!(!button.is_held());
+
+    // A press in progress counts from when tracking starts
+    button.set_hold_time(0.02).unwrap();
+    assert!(!button.is_held());
+    std::thread::sleep(Duration::from_millis(30));
+    assert!(button.is_held());
+    assert!(button.held_time().unwrap() >= 0.02);
+
+    pin.drive_high();
+    assert!(!button.is_held());
+    assert_eq!(button.held_time(), None);
+}
+
+#[test]
+fn press_stats_time_each_press() {
+    let factory = common::factory();
+    let mut button = Button::new(4);
+    button.set_press_stats(true).unwrap();
+    let pin = factory.get(4);
+    for hold in [10, 30] {
+        pin.drive_low();
+        std::thread::sleep(Duration::from_millis(hold));
+        pin.drive_high();
+    }
+    let stats = button.press_stats().unwrap();
+    assert_eq!(stats.count(), 2);
+    let (mean, max) = (
+        stats.mean_duration().unwrap(),
+        stats.max_duration().unwrap(),
+    );
+    assert!(mean >= Duration::from_millis(20) && mean < max);
+    assert_eq!(stats.last_duration(), Some(max));
+
+    button.set_press_stats(false).unwrap();
+    assert_eq!(button.press_stats(), None);
+}
+
+#[test]
+fn gestures_are_reported_in_order() {
+    let factory = common::factory();
+    let mut button = Button::new(5);
+    button.set_click_timeout(0.1).unwrap();
+    button.set_hold_time(0.2).unwrap();
+    let (sender, gestures) = mpsc::channel();
+    button
+        .when_gesture(move |gesture| sender.send(gesture).unwrap())
+        .unwrap();
+    let pin = factory.get(5);
+    let next = || gestures.recv_timeout(Duration::from_secs(1)).unwrap();
+
+    for _ in 0..2 {
+        pin.drive_low();
+        pin.drive_high();
+    }
+    assert_eq!(next(), Gesture::Click(2));
+
+    // A click followed by a long press within the click timeout reports both
+    pin.drive_low();
+    pin.drive_high();
+    pin.drive_low();
+    assert_eq!(next(), Gesture::Click(1));
+    assert_eq!(next(), Gesture::LongPress);
+    pin.drive_high();
+    assert_eq!(next(), Gesture::Release);
+    assert!(gestures.recv_timeout(Duration::from_millis(300)).is_err());
+}
+
+#[test]
+fn pull_up_button_events_are_active_when_pressed() {
+    let factory = common::factory();
+    let mut button = Button::new(7);
+    let (sender, events) = mpsc::channel();
+    button
+        .when_changed(move |event| sender.send(event).unwrap())
+        .unwrap();
+
+    // The pin of a pull-up button goes low when it is pressed
+    let pin = factory.get(7);
+    pin.drive_low();
+    std::thread::sleep(Duration::from_millis(20));
+    pin.drive_high();
+
+    let press = events.try_recv().unwrap();
+    let release = events.try_recv().unwrap();
+    assert!(press.active);
+    assert!(!release.active);
+    assert_eq!((press.pin, release.pin), (7, 7));
+    assert_eq!(press.since_previous, None);
+    let elapsed = release.timestamp - press.timestamp;
+    assert!(elapsed >= Duration::from_millis(20));
+    assert_eq!(release.since_previous, Some(elapsed));
+}
@@ -0,0 +1,29 @@
+use rust_gpiozero::pins::{InputPin, MockFactory, PinFactory, PullUpDown, Trigger};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn async_interrupt_can_clear_itself() {
+    let factory = MockFactory::new();
+    let pin: Arc<Mutex<Option<Box<dyn InputPin>>>> = Arc::new(Mutex::new(None));
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let mut input = factory.input(4, PullUpDown::Off).unwrap();
+    let shared = Arc::clone(&pin);
+    let count = Arc::clone(&calls);
+    input
+        .set_async_interrupt(
+            Trigger::Both,
+            Box::new(move |_| {
+                count.fetch_add(1, Ordering::SeqCst);
+                let mut pin = shared.lock().unwrap();
+                pin.as_mut().unwrap().clear_async_interrupt().unwrap();
+            }),
+        )
+        .unwrap();
+    pin.lock().unwrap().replace(input);
+
+    factory.get(4).drive_high();
+    factory.get(4).drive_low();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,342 @@
+mod common;
+
+use rust_gpiozero::tones::Tone;
+use rust_gpiozero::{Error, Motor, PwmMode, Servo, TonalBuzzer, LED, PWMLED, RGBLED};
+use std::iter;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn blink_with_infinite_time_stays_on() {
+    let factory = common::factory();
+    let mut led = LED::new(2);
+    let handle = led.blink_with(f32::INFINITY, 1.0, Some(1), true);
+    assert!(factory.get(2).wait_for_value(1.0, Duration::from_secs(1)));
+    assert!(!handle.wait_timeout(Duration::from_millis(20)));
+    led.off();
+    assert!(handle.is_finished());
+}
+
+#[test]
+fn pwm_blink_with_infinite_fade_starts() {
+    let factory = common::factory();
+    let mut led = PWMLED::new(3);
+    let handle = led.blink_with(0.0, 0.0, f32::INFINITY, f32::NAN, None, true);
+    assert!(factory.get(3).wait_for_value(0.0, Duration::from_secs(1)));
+    led.off().unwrap();
+    assert!(handle.is_finished());
+}
+
+#[test]
+fn source_delay_must_be_finite() {
+    let factory = common::factory();
+    let mut led = LED::new(4);
+    assert!(led.set_source_delay(f32::INFINITY).is_err());
+    assert!(led.set_source_delay(-1.0).is_err());
+    assert_eq!(led.source_delay(), 0.01);
+
+    // A long delay doesn't hold up clearing the source
+    led.set_source_delay(3600.0).unwrap();
+    led.set_source(std::iter::repeat(1.0));
+    assert!(factory.get(4).wait_for_value(1.0, Duration::from_secs(1)));
+    led.clear_source();
+}
+
+#[test]
+fn failed_blink_is_not_completed() {
+    let factory = common::factory();
+    let mut led = PWMLED::new(5);
+    factory.get(5).set_pwm_fails(true);
+    let handle = led.blink_with(0.01, 0.01, 0.0, 0.0, Some(2), false);
+    assert!(handle.is_finished());
+    assert!(!handle.is_completed());
+    assert!(handle.take_error().is_some());
+    factory.get(5).set_pwm_fails(false);
+}
+
+#[test]
+fn melody_rejects_infinite_duration() {
+    let _factory = common::factory();
+    let mut buzzer = TonalBuzzer::new(6);
+    let a4 = Tone::from_note("A4").unwrap();
+    assert!(matches!(
+        buzzer.play_melody(vec![(a4, f32::INFINITY)]),
+        Err(Error::OutOfRange { .. })
+    ));
+    assert!(buzzer.play_melody(vec![(a4, f32::NAN)]).is_err());
+}
+
+#[test]
+fn failed_melody_is_not_completed() {
+    let factory = common::factory();
+    let mut buzzer = TonalBuzzer::new(7);
+    factory.get(7).set_pwm_fails(true);
+    let a4 = Tone::from_note("A4").unwrap();
+    let handle = buzzer.play_melody(vec![(a4, 0.01)]).unwrap();
+    handle.wait();
+    assert!(!handle.is_completed());
+    assert!(handle.take_error().is_some());
+    factory.get(7).set_pwm_fails(false);
+}
+
+#[test]
+fn blink_from_completion_callback_does_not_deadlock() {
+    let _factory = common::factory();
+    let mut first = LED::new(8);
+    let mut second = LED::new(9);
+    let (sender, done) = mpsc::channel();
+    first
+        .blink_with(0.01, 0.01, Some(1), true)
+        .on_completed(move || {
+            let handle = second.blink_with(0.01, 0.01, Some(1), false);
+            second.on();
+            sender.send(handle.is_finished()).unwrap();
+        });
+    assert_eq!(done.recv_timeout(Duration::from_secs(1)), Ok(true));
+}
+
+#[test]
+fn motor_value_replaces_source() {
+    let factory = common::factory();
+    let mut motor = Motor::new(10, 11);
+    motor.set_source(iter::repeat(0.5));
+    assert!(factory.get(10).wait_for_value(0.5, Duration::from_secs(1)));
+    motor.set_value(-1.0).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(motor.value(), -1.0);
+
+    // Ramping to the current speed leaves it unchanged
+    motor.set_acceleration(Some(1.0)).unwrap();
+    motor.set_value(-1.0).unwrap();
+    assert_eq!(motor.value(), -1.0);
+    // An acceleration reaching the target within a step still reaches it
+    motor.set_acceleration(Some(f64::INFINITY)).unwrap();
+    motor.set_value(0.25).unwrap();
+    assert_eq!(motor.value(), 0.25);
+}
+
+#[test]
+fn dropped_motor_stops_ramping() {
+    let factory = common::factory();
+    let mut motor = Motor::new(14, 15);
+    motor.set_acceleration(Some(1.0)).unwrap();
+    motor.set_value(1.0).unwrap();
+    drop(motor);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(factory.get(14).value(), 0.0);
+}
+
+#[test]
+fn hardware_pwm_channel_is_claimed_once() {
+    let factory = common::factory();
+    let servo = Servo::new_with_pwm_mode(18, PwmMode::Hardware);
+    assert!(matches!(
+        PWMLED::try_new_with_pwm_mode(12, PwmMode::Hardware),
+        Err(Error::PinBusy(18))
+    ));
+    // Pin 12 shares the channel of pin 18, so it falls back to software PWM
+    let led = PWMLED::new(12);
+    assert!(!factory.get(12).is_hardware_pwm());
+
+    drop((servo, led));
+    let _led = PWMLED::new_with_pwm_mode(12, PwmMode::Hardware);
+    assert!(factory.get(12).is_hardware_pwm());
+}
+
+#[test]
+fn rgbled_channels_follow_pwm_mode() {
+    let factory = common::factory();
+    assert!(matches!(
+        RGBLED::try_new_with_pwm_mode(20, 21, 22, PwmMode::Hardware),
+        Err(Error::Unsupported(_))
+    ));
+    let mut led = RGBLED::new_with_pwm_mode(20, 21, 22, PwmMode::Software);
+    led.set_active_high(false);
+    led.set_color((0.25, 0.0, 1.0)).unwrap();
+    assert_eq!(factory.get(20).value(), 0.75);
+    assert_eq!(factory.get(22).value(), 0.0);
+    assert!(!factory.get(20).is_hardware_pwm());
+
+    led.set_blink_count(1);
+    led.blink(0.01, 0.01, 0.0, 0.0, (1.0, 1.0, 1.0), (0.0, 0.5, 0.0))
+        .unwrap();
+    led.wait();
+    assert_eq!(led.color(), (0.0, 0.5, 0.0));
+    drop(led);
+    assert_eq!(factory.get(21).value(), 1.0);
+}
+
+#[test]
+fn rgbled_without_pwm_uses_digital_outputs() {
+    let factory = common::factory();
+    let mut led = RGBLED::new_without_pwm(23, 24, 25);
+    assert!(led.set_color((0.5, 0.0, 0.0)).is_err());
+    assert!(led
+        .pulse(1.0, 1.0, (1.0, 1.0, 1.0), (0.0, 0.0, 0.0))
+        .is_err());
+    led.set_color((1.0, 0.0, 1.0)).unwrap();
+    assert_eq!(factory.get(23).value(), 1.0);
+    assert_eq!(factory.get(23).frequency(), None);
+    led.toggle().unwrap();
+    assert_eq!(led.color(), (0.0, 1.0, 0.0));
+}
+
+#[test]
+fn buzzer_tone_replaces_source() {
+    let factory = common::factory();
+    let mut buzzer = TonalBuzzer::new(26);
+    buzzer.set_source(iter::repeat(1.0));
+    assert!(factory.get(26).wait_for_value(0.5, Duration::from_secs(1)));
+    let a4 = Tone::from_note("A4").unwrap();
+    buzzer.play(a4).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(buzzer.tone(), Some(a4));
+}
+
+#[test]
+fn dropped_buzzer_stops_source() {
+    let factory = common::factory();
+    let mut buzzer = TonalBuzzer::new(27);
+    buzzer.set_source(iter::repeat(0.5));
+    assert!(factory.get(27).wait_for_value(0.5, Duration::from_secs(1)));
+    drop(buzzer);
+    let states = factory.get(27).history().len();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(factory.get(27).history().len(), states);
+    assert_eq!(factory.get(27).frequency(), None);
+}
+
+#[test]
+fn dropped_pwmled_stops_source() {
+    let factory = common::factory();
+    let mut led = PWMLED::new(28);
+    led.set_source(iter::repeat(0.5));
+    assert!(factory.get(28).wait_for_value(0.5, Duration::from_secs(1)));
+    drop(led);
+    let states = factory.get(28).history().len();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(factory.get(28).history().len(), states);
+    assert_eq!(factory.get(28).value(), 0.0);
+}
+
+#[test]
+fn setting_a_state_replaces_source() {
+    let factory = common::factory();
+    let mut led = LED::new(29);
+    led.set_source(iter::repeat(1.0));
+    assert!(factory.get(29).wait_for_value(1.0, Duration::from_secs(1)));
+    led.off();
+    let mut servo = Servo::new(30);
+    servo.set_source(iter::repeat(1.0));
+    assert!(factory.get(30).wait_for_value(0.1, Duration::from_secs(1)));
+    servo.min().unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(factory.get(29).value(), 0.0);
+    assert_eq!(factory.get(30).value(), 0.05);
+}
+
+#[test]
+fn pwm_value_changes_without_clearing_the_signal() {
+    let factory = common::factory();
+    let mut led = PWMLED::new_with_pwm_mode(19, PwmMode::Hardware);
+    led.set_value(0.5).unwrap();
+    led.set_value(0.25).unwrap();
+    led.on().unwrap();
+    assert_eq!(factory.get(19).states(), vec![0.0, 0.5, 0.25, 1.0]);
+}
+
+#[test]
+fn servo_rejects_conflicting_timings() {
+    let _factory = common::factory();
+    let mut servo = Servo::new(16);
+    assert!(matches!(
+        servo.set_min_pulse_width(2000),
+        Err(Error::InvalidPulseWidth(2000))
+    ));
+    assert!(matches!(
+        servo.set_max_pulse_width(20000),
+        Err(Error::InvalidPulseWidth(20000))
+    ));
+    assert!(matches!(
+        servo.set_frame_width(2),
+        Err(Error::InvalidPulseWidth(2000))
+    ));
+    assert_eq!(servo.get_frame_width(), 20);
+    servo.set_frame_width(3).unwrap();
+    assert_eq!(servo.get_frame_width(), 3);
+}
+
+#[test]
+fn pwm_frequency_applies_to_every_pattern() {
+    let factory = common::factory();
+    let pin = factory.get(17);
+    let mut led = PWMLED::new(17);
+    led.set_frequency(1000.0).unwrap();
+
+    led.set_blink_count(1);
+    led.blink(0.02, 0.02, 0.02, 0.02);
+    led.wait();
+    led.set_blink_count(1);
+    led.pulse(0.02, 0.02);
+    led.wait();
+    led.set_source(iter::repeat(0.5));
+    assert!(pin.wait_for_value(0.5, Duration::from_secs(1)));
+    led.clear_source();
+
+    let frequencies: Vec<_> = pin
+        .history()
+        .iter()
+        .filter_map(|state| state.frequency)
+        .collect();
+    assert!(frequencies.len() > 4);
+    assert!(frequencies.iter().all(|&frequency| frequency == 1000.0));
+
+    let mut motor = Motor::new(31, 32);
+    motor.set_frequency(20000.0).unwrap();
+    motor.set_value(-0.5).unwrap();
+    assert_eq!(factory.get(32).frequency(), Some(20000.0));
+    assert_eq!(factory.get(32).value(), 0.5);
+}
+
+#[test]
+fn blinks_with_the_same_period_run_in_lock_step() {
+    let factory = common::factory();
+    let mut first = LED::new(33);
+    let mut second = LED::new(34);
+    first.blink(0.05, 0.05);
+    thread::sleep(Duration::from_millis(30));
+    second.blink(0.05, 0.05);
+
+    // Started 30ms apart, the blinks would disagree over half of the time without lock-step
+    let mismatches = (0..300)
+        .filter(|_| {
+            thread::sleep(Duration::from_millis(1));
+            factory.get(33).value() != factory.get(34).value()
+        })
+        .count();
+    first.off();
+    second.off();
+    assert!(mismatches < 15, "{} mismatches", mismatches);
+}
+
+#[test]
+fn on_after_blink_writes_no_stale_frames() {
+    let factory = common::factory();
+    let mut led = LED::new(35);
+    let mut pwmled = PWMLED::new(36);
+    // Waiting without a blink returns immediately
+    led.wait();
+    pwmled.wait();
+
+    led.blink(0.01, 0.01);
+    pwmled.blink(0.01, 0.01, 0.02, 0.02);
+    thread::sleep(Duration::from_millis(25));
+    led.on();
+    pwmled.on().unwrap();
+    factory.get(35).clear_history();
+    factory.get(36).clear_history();
+    thread::sleep(Duration::from_millis(60));
+    assert_eq!(factory.get(35).states(), vec![1.0]);
+    assert_eq!(factory.get(36).states(), vec![1.0]);
+}
@@ -0,0 +1,9 @@
+use rust_gpiozero::tools::post_delayed;
+
+#[test]
+fn post_delayed_accepts_any_delay() {
+    let mut values = post_delayed(vec![1, 2], f32::INFINITY);
+    assert_eq!(values.next(), Some(1));
+    let values: Vec<_> = post_delayed(vec![1, 2], -1.0).collect();
+    assert_eq!(values, vec![1, 2]);
+}